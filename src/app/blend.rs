@@ -0,0 +1,186 @@
+// app/blend.rs
+//! Blend modes: exact per-pixel math for the CPU compositor, plus a
+//! single-color approximation for the live egui view, which can only do
+//! premultiplied "over" blending.
+
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+/// Reflectance floor for the pigment model; keeps absorbance finite.
+const MIN_REFLECTANCE: f32 = 0.001;
+
+/// How a layer or blot is combined with what lies beneath it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Additive,
+    /// Subtractive mixing of transparent pigments (Kubelka–Munk style):
+    /// absorbances add up, so overlaps darken like layered ink.
+    Pigment,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 5] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Additive,
+        BlendMode::Pigment,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BlendMode::Normal => "Normal",
+            BlendMode::Multiply => "Multiply",
+            BlendMode::Screen => "Screen",
+            BlendMode::Additive => "Additive",
+            BlendMode::Pigment => "Pigment",
+        }
+    }
+
+    /// Blend one straight (unpremultiplied) channel `src` onto `dst`.
+    pub fn mix(self, dst: f32, src: f32) -> f32 {
+        match self {
+            BlendMode::Normal => src,
+            BlendMode::Multiply => dst * src,
+            BlendMode::Screen => dst + src - dst * src,
+            BlendMode::Additive => (dst + src).min(1.0),
+            BlendMode::Pigment => reflectance(absorbance(dst) + absorbance(src)),
+        }
+    }
+
+    /// Composite premultiplied `src` over premultiplied `dst`.
+    pub fn composite(self, dst: [f32; 4], src: [f32; 4]) -> [f32; 4] {
+        let (ab, as_) = (dst[3], src[3]);
+        if as_ <= 0.0 {
+            return dst;
+        }
+        if self == BlendMode::Normal || ab <= 0.0 {
+            let inv = 1.0 - as_;
+            return [
+                src[0] + dst[0] * inv,
+                src[1] + dst[1] * inv,
+                src[2] + dst[2] * inv,
+                as_ + ab * inv,
+            ];
+        }
+
+        let mut out = [0.0; 4];
+        for c in 0..3 {
+            let mixed = self.mix(dst[c] / ab, src[c] / as_);
+            out[c] = src[c] * (1.0 - ab) + dst[c] * (1.0 - as_) + as_ * ab * mixed;
+        }
+        out[3] = as_ + ab * (1.0 - as_);
+        out
+    }
+
+    /// Color for egui's normal blending that approximates this mode. Exact for
+    /// `Normal` and `Additive`; the others use the source luminance as a
+    /// stand-in for per-channel alpha and are exact over white.
+    pub fn live_color(self, color: Color32) -> Color32 {
+        let a = color.a() as f32 / 255.0;
+        if a <= 0.0 {
+            return Color32::TRANSPARENT;
+        }
+        let straight = [
+            color.r() as f32 / 255.0 / a,
+            color.g() as f32 / 255.0 / a,
+            color.b() as f32 / 255.0 / a,
+        ];
+        let lum = 0.299 * straight[0] + 0.587 * straight[1] + 0.114 * straight[2];
+
+        // (premultiplied rgb, alpha) for "src + dst * (1 - alpha)"
+        let (rgb, alpha) = match self {
+            BlendMode::Normal => return color,
+            BlendMode::Additive => (straight.map(|c| c * a), 0.0),
+            BlendMode::Screen => (straight.map(|c| c * a), a * lum),
+            BlendMode::Multiply | BlendMode::Pigment => {
+                (straight.map(|c| (c - lum).max(0.0) * a), a * (1.0 - lum))
+            }
+        };
+
+        let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color32::from_rgba_premultiplied(byte(rgb[0]), byte(rgb[1]), byte(rgb[2]), byte(alpha))
+    }
+}
+
+/// Kubelka–Munk K/S ratio for a reflectance.
+fn absorbance(r: f32) -> f32 {
+    let r = r.clamp(MIN_REFLECTANCE, 1.0);
+    (1.0 - r) * (1.0 - r) / (2.0 * r)
+}
+
+/// Inverse of [`absorbance`].
+fn reflectance(ks: f32) -> f32 {
+    1.0 + ks - (ks * ks + 2.0 * ks).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-4;
+
+    fn close(a: [f32; 4], b: [f32; 4], eps: f32) -> bool {
+        a.iter().zip(&b).all(|(x, y)| (x - y).abs() < eps)
+    }
+
+    #[test]
+    fn channel_formulas() {
+        let (dst, src) = (0.5, 0.4);
+        assert!((BlendMode::Normal.mix(dst, src) - 0.4).abs() < EPS);
+        assert!((BlendMode::Multiply.mix(dst, src) - 0.2).abs() < EPS);
+        assert!((BlendMode::Screen.mix(dst, src) - 0.7).abs() < EPS);
+        assert_eq!(BlendMode::Additive.mix(0.8, 0.4), 1.0);
+
+        // Clear pigment leaves the paper alone; layered ink darkens
+        let pigment = BlendMode::Pigment;
+        assert!((pigment.mix(dst, 1.0) - dst).abs() < EPS);
+        assert!((pigment.mix(1.0, src) - src).abs() < EPS);
+        assert!(pigment.mix(dst, dst) < dst);
+    }
+
+    #[test]
+    fn composite_by_source_alpha() {
+        let dst = [0.5, 0.5, 0.5, 1.0];
+        let src = [0.4, 0.2, 0.0, 1.0];
+        for mode in BlendMode::ALL {
+            // Nothing to add
+            assert_eq!(mode.composite(dst, [0.0; 4]), dst, "{mode:?}");
+
+            // Opaque: the mixed color
+            let mixed = [0, 1, 2].map(|c| mode.mix(dst[c], src[c]));
+            let out = mode.composite(dst, src);
+            assert!(close(out, [mixed[0], mixed[1], mixed[2], 1.0], EPS), "{mode:?}: {out:?}");
+
+            // Half: halfway between backdrop and mix
+            let half = mode.composite(dst, src.map(|c| c * 0.5));
+            let expected = [0, 1, 2].map(|c| (dst[c] + mixed[c]) * 0.5);
+            let expected = [expected[0], expected[1], expected[2], 1.0];
+            assert!(close(half, expected, EPS), "{mode:?}: {half:?}");
+
+            // Onto nothing every mode acts like normal
+            assert_eq!(mode.composite([0.0; 4], src), src, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn live_multiply_matches_the_compositor_over_white() {
+        let white = [1.0; 4];
+        for alpha in [0, 128, 255] {
+            let gray = Color32::from_rgba_unmultiplied(102, 102, 102, alpha);
+            let src = [gray.r(), gray.g(), gray.b(), gray.a()].map(|c| c as f32 / 255.0);
+            let exact = BlendMode::Multiply.composite(white, src);
+
+            let live = BlendMode::Multiply.live_color(gray);
+            let live = [live.r(), live.g(), live.b(), live.a()].map(|c| c as f32 / 255.0);
+            let over = BlendMode::Normal.composite(white, live);
+            assert!(close(over, exact, 2.0 / 255.0), "{alpha}: {over:?} vs {exact:?}");
+        }
+        let red = Color32::from_rgb(200, 0, 0);
+        assert_eq!(BlendMode::Normal.live_color(red), red);
+    }
+}
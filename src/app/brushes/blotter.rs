@@ -1,22 +1,35 @@
 // src/app/brushes/blotter.rs
-use eframe::egui::{Color32, Pos2};
+use std::any::Any;
+
+use eframe::egui::{Color32, Pos2, Ui};
+use serde::{Deserialize, Serialize};
+use crate::app::blend::BlendMode;
 use crate::app::brushes::blotter_props::BlotterProps;
+use crate::app::brushes::{BrushContext, BrushEngine, BrushKind, CanvasElement};
+use crate::app::simulation;
+use crate::app::ui::brush_props;
+use crate::app::utils::path::SpeedSampler;
 
 /// A single paint blot placed on the canvas.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Blot {
     pub pos: Pos2,
     pub radius: f32,
     pub color: Color32,
     pub softness: f32,
     pub opacity: f32,
+    /// How the blot mixes with paint beneath it.
+    #[serde(default)]
+    pub blend: BlendMode,
 }
 
 /// Tick-based engine that generates blots along the stroke path.
 #[derive(Clone)]
 pub struct Blotter {
-    /// Last point where movement was sampled.
-    last_pos: Option<Pos2>,
+    pub props: BlotterProps,
+
+    /// Pointer speed, and the last point where movement was sampled.
+    sampler: SpeedSampler,
 
     /// Distance accumulated since last deposit.
     stroke_accum: f32,
@@ -25,35 +38,34 @@ pub struct Blotter {
 impl Blotter {
     pub fn new() -> Self {
         Self {
-            last_pos: None,
+            props: BlotterProps::default(),
+            sampler: SpeedSampler::default(),
             stroke_accum: 0.0,
         }
     }
 
     /// Called when the stroke starts.
-    pub fn begin_stroke(&mut self, pos: Pos2) {
-        self.last_pos = Some(pos);
+    pub fn begin_stroke(&mut self, pos: Pos2, time: f64) {
+        self.sampler.begin(pos, time);
         self.stroke_accum = 0.0;
     }
 
     /// Called when the stroke ends.
     pub fn end_stroke(&mut self) {
-        self.last_pos = None;
+        self.sampler.end();
         self.stroke_accum = 0.0;
     }
 
     /// Called every frame while the brush is moving.
-    /// Returns newly generated blots.
-    pub fn tick(
-        &mut self,
-        current_pos: Pos2,
-        props: &BlotterProps,
-        current_color: Color32,
-    ) -> Vec<Blot> {
+    /// Returns newly generated blots, shaped by pointer speed and pressure.
+    pub fn deposit(&mut self, current_pos: Pos2, ctx: &BrushContext) -> Vec<Blot> {
+        let props = &self.props;
+        let mut rng = simulation::rng_from(ctx.seed);
         let mut new_blots = Vec::new();
 
-        let Some(last) = self.last_pos else {
-            self.last_pos = Some(current_pos);
+        let last = self.sampler.last_pos();
+        self.sampler.feed(current_pos, ctx.time);
+        let Some(last) = last else {
             return new_blots;
         };
 
@@ -76,17 +88,56 @@ impl Blotter {
             let blot_x = last.x + dx * t;
             let blot_y = last.y + dy * t;
 
-            new_blots.push(Blot {
-                pos: Pos2::new(blot_x, blot_y),
-                radius: props.radius,
-                color: current_color,
-                softness: props.softness,
-                opacity: props.opacity,
-            });
+            new_blots.push(props.dynamic_blot(
+                Pos2::new(blot_x, blot_y),
+                ctx.color,
+                self.sampler.speed(),
+                ctx.pressure,
+                &mut rng,
+            ));
         }
 
-        self.last_pos = Some(current_pos);
-
         new_blots
     }
 }
+
+impl BrushEngine for Blotter {
+    fn kind(&self) -> BrushKind {
+        BrushKind::BLOTTER
+    }
+
+    fn label(&self) -> &'static str {
+        "Blotter"
+    }
+
+    fn begin(&mut self, ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        self.begin_stroke(pos, ctx.time);
+        Vec::new()
+    }
+
+    fn drag(&mut self, ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        let blots = self.deposit(pos, ctx);
+        if blots.is_empty() {
+            Vec::new()
+        } else {
+            vec![CanvasElement::Blots(blots)]
+        }
+    }
+
+    fn end(&mut self, _ctx: &BrushContext) -> Vec<CanvasElement> {
+        self.end_stroke();
+        Vec::new()
+    }
+
+    fn properties_ui(&mut self, ui: &mut Ui, color: Color32) {
+        brush_props::blotter(ui, &mut self.props, color);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -1,9 +1,15 @@
 // src/app/brushes/blotter_props.rs
+use eframe::egui::{ecolor::Hsva, Color32, Pos2, Vec2};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::app::blend::BlendMode;
+use crate::app::brushes::blotter::Blot;
+
 /// User-adjustable parameters for the blotter brush.
 /// These map directly to Blot fields and brush behavior.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BlotterProps {
     /// Radius of each blot (in points).
     pub radius: f32,
@@ -16,6 +22,39 @@ pub struct BlotterProps {
 
     /// Minimum distance traveled before depositing the next blot.
     pub spacing: f32,
+
+    /// How new blots mix with the paint beneath them.
+    pub blend: BlendMode,
+
+    /// Pointer speed (points/s) treated as full speed by the dynamics.
+    pub max_speed: f32,
+
+    /// Exponent applied to normalized speed: < 1 reacts early, > 1 late.
+    pub speed_curve: f32,
+
+    /// Radius change at full speed: -1 = vanishes, 0 = none, 1 = doubles.
+    pub speed_radius: f32,
+
+    /// Opacity change at full speed, same scale as `speed_radius`.
+    pub speed_opacity: f32,
+
+    /// Exponent applied to pen/touch force.
+    pub pressure_curve: f32,
+
+    /// How much light pressure shrinks the radius (0 = ignore pressure).
+    pub pressure_radius: f32,
+
+    /// How much light pressure fades the opacity (0 = ignore pressure).
+    pub pressure_opacity: f32,
+
+    /// Random radius variation per blot, as a fraction of the radius.
+    pub size_jitter: f32,
+
+    /// Random offset from the path, as a multiple of the radius.
+    pub scatter: f32,
+
+    /// Random hue shift per blot, in degrees either way.
+    pub hue_jitter: f32,
 }
 
 impl Default for BlotterProps {
@@ -25,6 +64,75 @@ impl Default for BlotterProps {
             softness: 0.15,
             opacity: 0.9,
             spacing: 4.0, // moderately dense
+            blend: BlendMode::Normal,
+
+            // dynamics start neutral
+            max_speed: 1500.0,
+            speed_curve: 1.0,
+            speed_radius: 0.0,
+            speed_opacity: 0.0,
+            pressure_curve: 1.0,
+            pressure_radius: 0.0,
+            pressure_opacity: 0.0,
+            size_jitter: 0.0,
+            scatter: 0.0,
+            hue_jitter: 0.0,
+        }
+    }
+}
+
+impl BlotterProps {
+    /// Shape one blot at `pos` from pointer `speed` (points/s) and pen
+    /// `pressure` (0–1, `None` when the device doesn't report force).
+    pub fn dynamic_blot<R: Rng>(
+        &self,
+        pos: Pos2,
+        color: Color32,
+        speed: f32,
+        pressure: Option<f32>,
+        rng: &mut R,
+    ) -> Blot {
+        let speed_t = (speed / self.max_speed.max(1.0))
+            .clamp(0.0, 1.0)
+            .powf(self.speed_curve.max(0.05));
+        let pressure_t = pressure
+            .map(|p| p.clamp(0.0, 1.0).powf(self.pressure_curve.max(0.05)))
+            .unwrap_or(1.0);
+
+        let mut radius = self.radius
+            * (1.0 + self.speed_radius * speed_t)
+            * (1.0 - self.pressure_radius * (1.0 - pressure_t));
+        let opacity = self.opacity
+            * (1.0 + self.speed_opacity * speed_t)
+            * (1.0 - self.pressure_opacity * (1.0 - pressure_t));
+
+        if self.size_jitter > 0.0 {
+            radius *= 1.0 + self.size_jitter * rng.random_range(-1.0..=1.0);
+        }
+        let radius = radius.max(0.5);
+
+        let mut pos = pos;
+        if self.scatter > 0.0 {
+            let angle = rng.random_range(0.0..std::f32::consts::TAU);
+            let dist = self.scatter * radius * rng.random::<f32>().sqrt();
+            pos += Vec2::angled(angle) * dist;
+        }
+
+        let mut color = color;
+        if self.hue_jitter > 0.0 {
+            let mut hsva = Hsva::from(color);
+            let shift = self.hue_jitter / 360.0 * rng.random_range(-1.0..=1.0);
+            hsva.h = (hsva.h + shift).rem_euclid(1.0);
+            color = hsva.into();
+        }
+
+        Blot {
+            pos,
+            radius,
+            color,
+            softness: self.softness,
+            opacity: opacity.clamp(0.0, 1.0),
+            blend: self.blend,
         }
     }
 }
@@ -1,21 +1,59 @@
 // app/brushes/crystal.rs
 
-use eframe::egui::{Painter, Pos2, Stroke, Color32, Vec2};
-use crate::app::brushes::crystal_props::CrystalProps;
+use std::any::Any;
+
+use eframe::egui::{Pos2, Rect, Color32, Ui, Vec2};
+use eframe::emath::Rot2;
+use rand::Rng;
+use crate::app::simulation::{self, SimRng};
+use serde::{Deserialize, Serialize};
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::{BrushContext, BrushEngine, BrushKind, CanvasElement, CanvasMut, TickContext};
+use crate::app::brushes::crystal_props::{CrystalProps, Smoothing};
+use crate::app::spatial::SceneIndex;
+use crate::app::ui::brush_props;
+use crate::app::utils::math::segment_intersection;
+use crate::app::utils::path::{self, SpeedSampler, Stabilizer};
 
 /// A single crystal segment.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Segment {
     pub start: Pos2,
     pub end: Pos2,
     pub dir: Vec2,
-    pub born: std::time::Instant,
+    /// Canvas clock time (seconds) at which the segment was created.
+    #[serde(default)]
+    pub born: f64,
     pub generation: u8,
     pub growing: bool,
+    /// Index (in the stroke) of the segment this one grew from.
+    #[serde(default)]
+    pub parent: Option<u32>,
+    /// Remaining opacity; lowered as the segment fades during decay.
+    #[serde(default = "full_opacity")]
+    pub opacity: f32,
+    /// Width at the start and end, as multiples of the stroke's thickness.
+    #[serde(default = "full_width")]
+    pub width: [f32; 2],
+}
+
+fn full_opacity() -> f32 {
+    1.0
+}
+
+fn full_width() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+impl Segment {
+    /// Width multiple at fraction `t` of the way from start to end.
+    pub fn width_at(&self, t: f32) -> f32 {
+        self.width[0] + (self.width[1] - self.width[0]) * t.clamp(0.0, 1.0)
+    }
 }
 
 /// A stroke consisting of one or more connected crystal segments.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StrokeData {
     pub segments: Vec<Segment>,
     pub color: Color32,
@@ -31,55 +69,642 @@ impl StrokeData {
         }
     }
 
-    pub fn add_segment(&mut self, start: Pos2, end: Pos2, dir: Vec2) {
+    /// Append a segment as the new growing tip; the previous tip stops growing.
+    pub fn add_segment(&mut self, start: Pos2, end: Pos2, dir: Vec2, width: [f32; 2], born: f64) {
+        let parent = self.segments.len().checked_sub(1).map(|i| i as u32);
+        if let Some(prev) = self.segments.last_mut() {
+            prev.growing = false;
+        }
+
         self.segments.push(Segment {
             start,
             end,
             dir,
-            born: std::time::Instant::now(),
+            born,
             generation: 0,
             growing: true,
+            parent,
+            opacity: 1.0,
+            width,
+        });
+    }
+
+    /// Drop the segments flagged in `remove`, keeping `parent` links valid.
+    /// Segments whose parent is removed become roots.
+    pub fn remove_segments(&mut self, remove: &[bool]) {
+        let gone = |i: usize| remove.get(i).copied().unwrap_or(false);
+
+        let mut new_index = Vec::with_capacity(self.segments.len());
+        let mut kept = 0;
+        for i in 0..self.segments.len() {
+            new_index.push((!gone(i)).then_some(kept));
+            kept += u32::from(!gone(i));
+        }
+
+        let mut i = 0;
+        self.segments.retain(|_| {
+            i += 1;
+            !gone(i - 1)
+        });
+        for seg in &mut self.segments {
+            seg.parent = seg.parent.and_then(|p| new_index.get(p as usize).copied().flatten());
+        }
+    }
+}
+
+/// Chaikin passes applied to a released path.
+const CHAIKIN_PASSES: u32 = 2;
+
+/// Catmull-Rom sampling step, as a fraction of `min_segment`.
+const CURVE_STEP: f32 = 0.25;
+
+/// Full segment length, in multiples of `min_segment`, before a tip hands over.
+const SEGMENT_SPAN: f32 = 4.0;
+
+/// Chance per point of growth that a generation-0 segment sprouts a side branch.
+const BRANCH_RATE: f32 = 0.12;
+
+/// Maximum random turn (radians) when a tip continues as a new segment.
+const TIP_JITTER: f32 = 0.12;
+
+/// Branches deeper than this never sprout further children.
+const MAX_GENERATION: u8 = 6;
+
+/// Thinnest a branch gets, as a multiple of the stroke's thickness.
+const MIN_WIDTH: f32 = 0.05;
+
+/// Growth stops spawning once a stroke holds this many segments.
+const MAX_SEGMENTS_PER_STROKE: usize = 4000;
+
+/// Opacity a fresh segment loses per point of decay at `decay_fade` 1.
+const FADE_RATE: f32 = 0.02;
+
+/// Age (seconds) at which a segment fades twice as fast as a fresh one.
+const FADE_AGE_SCALE: f32 = 10.0;
+
+/// Limits applied to growing tips when "Contain" is on.
+pub struct Containment<'a> {
+    /// Tips may not leave this rectangle.
+    pub bounds: Rect,
+    /// Tips stop when they enter any of these blots.
+    pub blots: &'a [Blot],
+    /// Index over the strokes and blots as they were before this step.
+    pub index: &'a SceneIndex,
+}
+
+impl Containment<'_> {
+    /// Where a tip moving from `from` to `to` first runs into something, if anywhere.
+    fn first_hit(&self, stroke_idx: usize, from: Pos2, to: Pos2) -> Option<Pos2> {
+        if !self.bounds.contains(to) {
+            return Some(self.bounds.clamp(to));
+        }
+
+        let path = Rect::from_two_pos(from, to);
+
+        let entered_blot = self.index.blots_in(path, self.blots).into_iter().any(|i| {
+            let b = &self.blots[i];
+            b.pos.distance_sq(to) < b.radius * b.radius
+                && b.pos.distance_sq(from) >= b.radius * b.radius
         });
+        if entered_blot {
+            return Some(from);
+        }
+
+        self.index
+            .segments_in(path)
+            .into_iter()
+            .filter(|s| s.stroke != stroke_idx)
+            .filter_map(|s| segment_intersection(from, to, s.start, s.end))
+            .min_by(|p, q| from.distance_sq(*p).total_cmp(&from.distance_sq(*q)))
     }
 }
 
 /// The main crystal brush engine.
 pub struct CrystalBrush {
     pub props: CrystalProps,
+
+    /// Stabilized pointer path of the stroke being drawn.
+    points: Vec<Pos2>,
+
+    /// Width multiple from pressure and speed at each of `points`.
+    widths: Vec<f32>,
+
+    /// Steadies pointer input while drawing.
+    stabilizer: Stabilizer,
+
+    /// Raw pointer speed, for `speed_width`.
+    sampler: SpeedSampler,
 }
 
 impl CrystalBrush {
     pub fn new() -> Self {
         Self {
             props: CrystalProps::default(),
+            points: Vec::new(),
+            widths: Vec::new(),
+            stabilizer: Stabilizer::new(1, 0.0),
+            sampler: SpeedSampler::default(),
+        }
+    }
+
+    /// Smooth and respace a released path according to the props. With
+    /// smoothing and resampling off the path is returned unchanged.
+    fn finish_path(&self, points: &[Pos2]) -> Vec<Pos2> {
+        let min_segment = self.props.min_segment.max(0.5);
+        let points = match self.props.smoothing {
+            Smoothing::None => points.to_vec(),
+            Smoothing::Chaikin => path::chaikin(points, CHAIKIN_PASSES),
+            Smoothing::CatmullRom => path::catmull_rom(points, min_segment * CURVE_STEP),
+        };
+        if self.props.resample {
+            path::resample(&points, min_segment)
+        } else {
+            points
         }
     }
 
-    /// Very simple linear growth: extend the endpoint of each segment along its direction.
-    pub fn growth_step(&mut self, strokes: &mut Vec<StrokeData>, speed: f32, _contain: bool) {
+    /// Width multiple at each point of the finished `path`: the input widths
+    /// recorded along the drawn path, tapered towards both ends.
+    fn path_widths(&self, drawn: &[Pos2], path: &[Pos2]) -> Vec<f32> {
+        let lengths = path::arc_lengths(path);
+        let total = lengths.last().copied().unwrap_or(0.0);
+        path::transfer(drawn, &self.widths, path)
+            .into_iter()
+            .zip(lengths)
+            .map(|(w, along)| (w * self.props.taper(along, total)).max(MIN_WIDTH))
+            .collect()
+    }
+
+    /// Dendritic growth step.
+    ///
+    /// Every growing segment extends along `dir`. Once longer than
+    /// `min_segment` it may sprout a child branch at ±`branch_angle`; when it
+    /// reaches its full length it stops growing and may hand over to a new tip.
+    /// Child length, branch chance and continuation chance all shrink by
+    /// `branch_decay` per generation, so side branches die out. Branches are
+    /// `branch_width` as wide as the tip they sprout from; continuations keep
+    /// the tip's width.
+    ///
+    /// With `contain` set, tips stop at the canvas bounds and when they run
+    /// into another stroke's segment or enter a blot. A negative `speed`
+    /// decays instead, see [`Self::decay_step`].
+    pub fn growth_step(
+        &mut self,
+        strokes: &mut [StrokeData],
+        speed: f32,
+        contain: Option<&Containment>,
+        now: f64,
+        rng: &mut SimRng,
+    ) {
+        if speed < 0.0 {
+            self.decay_step(strokes, -speed * 0.5, now);
+            return;
+        }
+
+        let props = &self.props;
+        let min_segment = props.min_segment.max(0.5);
+        let step = speed * 0.5;
+
+        for (stroke_idx, stroke) in strokes.iter_mut().enumerate() {
+            let mut spawned = Vec::new();
+            let room = stroke.segments.len() < MAX_SEGMENTS_PER_STROKE;
+
+            let growing = stroke.segments.iter_mut().enumerate().filter(|(_, s)| s.growing);
+            for (seg_idx, seg) in growing {
+                let decay = props.branch_decay.clamp(0.0, 1.0).powi(seg.generation as i32);
+                let next = seg.end + seg.dir * step;
+
+                if let Some(contain) = contain {
+                    if let Some(hit) = contain.first_hit(stroke_idx, seg.end, next) {
+                        seg.end = hit;
+                        seg.growing = false;
+                        continue;
+                    }
+                }
+
+                seg.end = next;
+                let len = (seg.end - seg.start).length();
+
+                // Side branch
+                if room
+                    && len > min_segment
+                    && seg.generation < MAX_GENERATION
+                    && rng.random::<f32>() < BRANCH_RATE * step * decay
+                {
+                    let side = if rng.random_bool(0.5) { 1.0 } else { -1.0 };
+                    let width = (seg.width[1] * props.branch_width.clamp(0.0, 1.0)).max(MIN_WIDTH);
+                    spawned.push(Segment {
+                        start: seg.end,
+                        end: seg.end,
+                        dir: Rot2::from_angle(side * props.branch_angle) * seg.dir,
+                        born: now,
+                        generation: seg.generation + 1,
+                        growing: true,
+                        parent: Some(seg_idx as u32),
+                        opacity: 1.0,
+                        width: [width; 2],
+                    });
+                }
+
+                // Terminate at full length, possibly continuing with a fresh tip
+                let max_len = (min_segment * SEGMENT_SPAN * decay).max(min_segment);
+                if len >= max_len {
+                    seg.growing = false;
+
+                    if room && rng.random::<f32>() < decay {
+                        let jitter = rng.random_range(-TIP_JITTER..=TIP_JITTER);
+                        spawned.push(Segment {
+                            start: seg.end,
+                            end: seg.end,
+                            dir: Rot2::from_angle(jitter) * seg.dir,
+                            born: now,
+                            generation: seg.generation,
+                            growing: true,
+                            parent: Some(seg_idx as u32),
+                            opacity: 1.0,
+                            width: [seg.width[1]; 2],
+                        });
+                    }
+                }
+            }
+
+            stroke.segments.extend(spawned);
+        }
+    }
+
+    /// Decay step: growth in reverse, shrinking each stroke by `amount` points.
+    ///
+    /// Only segments nothing grew from retract, end first, so tips pull back
+    /// along their path and side branches are gone before the segment they
+    /// sprouted from starts shrinking. A segment is removed once fully
+    /// retracted or faded out; with `decay_fade` set every segment also fades,
+    /// older ones (by `born`) faster. Strokes can end up empty; the caller
+    /// prunes them.
+    pub fn decay_step(&mut self, strokes: &mut [StrokeData], amount: f32, now: f64) {
+        let fade = self.props.decay_fade.max(0.0) * FADE_RATE * amount;
+
         for stroke in strokes.iter_mut() {
-            if let Some(last) = stroke.segments.last_mut() {
-                let step = speed * 0.5;
-                last.end = last.end + last.dir * step;
+            let mut children = vec![0u32; stroke.segments.len()];
+            for seg in &stroke.segments {
+                if let Some(count) = seg.parent.and_then(|p| children.get_mut(p as usize)) {
+                    *count += 1;
+                }
+            }
+
+            let mut remove = vec![false; stroke.segments.len()];
+            for (i, seg) in stroke.segments.iter_mut().enumerate() {
+                if fade > 0.0 {
+                    let age = (now - seg.born).max(0.0) as f32;
+                    seg.opacity = (seg.opacity - fade * (1.0 + age / FADE_AGE_SCALE)).max(0.0);
+                }
+                if children[i] > 0 {
+                    continue;
+                }
+
+                let span = seg.end - seg.start;
+                let len = span.length();
+                if len <= amount || seg.opacity <= 0.0 {
+                    remove[i] = true;
+                } else {
+                    seg.width[1] = seg.width_at(1.0 - amount / len);
+                    seg.end -= span / len * amount;
+                }
+            }
+
+            if remove.contains(&true) {
+                stroke.remove_segments(&remove);
             }
         }
     }
 }
 
-impl crate::app::brushes::BrushEngine for CrystalBrush {
-    fn stroke(&mut self, painter: &Painter, from: Pos2, to: Pos2, color: Color32) {
-        let dv = to - from;
+impl BrushEngine for CrystalBrush {
+    fn kind(&self) -> BrushKind {
+        BrushKind::CRYSTAL
+    }
 
-        let dir = if dv.length_sq() > 0.0001 {
-            dv.normalized()
-        } else {
-            Vec2::new(1.0, 0.0)
+    fn label(&self) -> &'static str {
+        "Crystal"
+    }
+
+    fn begin(&mut self, ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        self.points.clear();
+        self.widths.clear();
+        self.stabilizer = Stabilizer::new(self.props.input_average, self.props.lazy_radius);
+        self.stabilizer.begin(pos);
+        self.sampler.begin(pos, ctx.time);
+        self.points.push(pos);
+        self.widths.push(self.props.input_width(0.0, ctx.pressure));
+        Vec::new()
+    }
+
+    fn drag(&mut self, ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        self.sampler.feed(pos, ctx.time);
+
+        if let Some(pos) = self.stabilizer.feed(pos) {
+            if self.points.last() != Some(&pos) {
+                self.points.push(pos);
+                self.widths.push(self.props.input_width(self.sampler.speed(), ctx.pressure));
+            }
+        }
+        Vec::new()
+    }
+
+    /// Turn the recorded pointer path into a growable stroke, smoothed and
+    /// respaced per the props.
+    fn end(&mut self, ctx: &BrushContext) -> Vec<CanvasElement> {
+        let mut drawn = std::mem::take(&mut self.points);
+        if let Some(last) = self.stabilizer.finish() {
+            if let (Some(&end), Some(&width)) = (drawn.last(), self.widths.last()) {
+                if end != last {
+                    drawn.push(last);
+                    self.widths.push(width);
+                }
+            }
+        }
+        self.sampler.end();
+
+        let points = self.finish_path(&drawn);
+        if points.len() < 2 {
+            return Vec::new();
+        }
+        let widths = self.path_widths(&drawn, &points);
+
+        let mut data = StrokeData::new(ctx.color, true);
+        data.thickness = Some(self.props.thickness);
+        for (i, w) in points.windows(2).enumerate() {
+            let a = w[0];
+            let b = w[1];
+            let dv = b - a;
+            let dir = if dv.length_sq() > 1e-6 {
+                dv.normalized()
+            } else {
+                Vec2::new(1.0, 0.0)
+            };
+            data.add_segment(a, b, dir, [widths[i], widths[i + 1]], ctx.time);
+        }
+
+        vec![CanvasElement::Stroke(data)]
+    }
+
+    fn tick(&mut self, canvas: &mut CanvasMut, ctx: &TickContext) -> bool {
+        let Some(speed) = ctx.growth else {
+            return false;
         };
 
-        // immediate one-segment stroke (preview style)
-        painter.line_segment(
-            [from, to],
-            Stroke::new(self.props.thickness, color),
-        );
+        let containment = ctx.contain.map(|bounds| Containment {
+            bounds,
+            blots: canvas.blots,
+            index: canvas.index,
+        });
+        let mut rng = simulation::rng_from(ctx.seed);
+        self.growth_step(canvas.strokes, speed, containment.as_ref(), ctx.time, &mut rng);
+        // Decay retracts and removes segments; growth only extends them
+        speed < 0.0
+    }
+
+    fn preview(&self) -> &[Pos2] {
+        &self.points
+    }
+
+    fn properties_ui(&mut self, ui: &mut Ui, color: Color32) {
+        brush_props::crystal(ui, &mut self.props, color);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::blend::BlendMode;
+    use crate::app::selection::Selection;
+    use crate::app::state::AppState;
+    use crate::app::utils::math::distance_to_segment;
+
+    fn ctx(time: f64) -> BrushContext {
+        BrushContext {
+            color: Color32::WHITE,
+            time,
+            pressure: None,
+            seed: 0,
+        }
+    }
+
+    /// Total length of the stroke's segments.
+    fn length(stroke: &StrokeData) -> f32 {
+        stroke.segments.iter().map(|s| s.start.distance(s.end)).sum()
+    }
+
+    #[test]
+    fn decay_retracts_strokes_to_nothing() {
+        let mut stroke = StrokeData::new(Color32::WHITE, true);
+        stroke.add_segment(Pos2::ZERO, Pos2::new(10.0, 0.0), Vec2::X, [1.0; 2], 0.0);
+        stroke.add_segment(Pos2::new(10.0, 0.0), Pos2::new(20.0, 0.0), Vec2::X, [1.0; 2], 0.0);
+        let mut strokes = [stroke];
+
+        let mut brush = CrystalBrush::new();
+        brush.decay_step(&mut strokes, 4.0, 0.0);
+        assert!((length(&strokes[0]) - 16.0).abs() < 1e-4);
+        assert_eq!(strokes[0].segments[0].end, Pos2::new(10.0, 0.0));
+
+        // The tip goes first, then the segment it grew from
+        brush.decay_step(&mut strokes, 8.0, 0.0);
+        assert_eq!(strokes[0].segments.len(), 1);
+        brush.decay_step(&mut strokes, 20.0, 0.0);
+        assert!(strokes[0].segments.is_empty());
+    }
+
+    #[test]
+    fn decayed_strokes_are_pruned_from_history_and_selection() {
+        let mut state = AppState::without_user_config();
+        for (y, len) in [(0.0, 200.0), (50.0, 20.0), (100.0, 200.0)] {
+            state.pointer_down(Pos2::new(0.0, y));
+            for x in 1..=10 {
+                state.pointer_drag(Pos2::new(x as f32 * len / 10.0, y));
+            }
+            state.pointer_up();
+        }
+        assert_eq!(state.layers[0].strokes.len(), 3);
+        state.selection = Some(Selection {
+            layer: 0,
+            strokes: vec![2],
+            blots: Vec::new(),
+        });
+
+        state.paused = false;
+        state.growth_speed = -2.0;
+        for _ in 0..100 {
+            if state.layers[0].strokes.len() < 3 {
+                break;
+            }
+            state.step_simulation(None);
+        }
+        state.paused = true;
+        let start_y = |state: &AppState| -> Vec<f32> {
+            state.layers[0].strokes.iter().map(|s| s.segments[0].start.y).collect()
+        };
+        assert_eq!(start_y(&state), [0.0, 100.0]);
+        assert_eq!(state.selection.as_ref().unwrap().strokes, [1]);
+
+        // Undo takes off the last stroke drawn, redo brings it back
+        state.undo();
+        assert_eq!(start_y(&state), [0.0]);
+        state.redo();
+        assert_eq!(start_y(&state), [0.0, 100.0]);
+        state.undo();
+        state.undo();
+        assert!(state.layers[0].strokes.is_empty());
+        assert!(!state.history.can_undo());
+    }
+
+    #[test]
+    fn strokes_start_where_the_pointer_went_down() {
+        let mut brush = CrystalBrush::new();
+        brush.props.smoothing = Smoothing::None;
+        brush.props.input_average = 4;
+        brush.begin(&ctx(0.0), Pos2::new(10.0, 10.0));
+        for x in [20.0, 30.0, 40.0, 50.0] {
+            brush.drag(&ctx(0.0), Pos2::new(x, 10.0));
+        }
+        let elements = brush.end(&ctx(0.0));
+
+        let [CanvasElement::Stroke(stroke)] = elements.as_slice() else {
+            panic!("one stroke expected");
+        };
+        assert_eq!(stroke.segments[0].start, Pos2::new(10.0, 10.0));
+        assert_eq!(stroke.segments.last().unwrap().end, Pos2::new(50.0, 10.0));
+    }
+
+    /// A stroke of one growing segment from the origin along +x.
+    fn seed_stroke() -> StrokeData {
+        let mut stroke = StrokeData::new(Color32::WHITE, true);
+        stroke.add_segment(Pos2::ZERO, Pos2::new(1.0, 0.0), Vec2::X, [1.0; 2], 0.0);
+        stroke
+    }
+
+    #[test]
+    fn growth_branches_at_the_branch_angle() {
+        let mut brush = CrystalBrush::new();
+        let mut strokes = [seed_stroke()];
+        let mut rng = simulation::rng_from(7);
+        for _ in 0..300 {
+            brush.growth_step(&mut strokes, 2.0, None, 0.0, &mut rng);
+        }
+
+        let segments = &strokes[0].segments;
+        assert!(segments.iter().any(|s| s.generation > 0), "no branches grew");
+        let props = &brush.props;
+        for seg in segments {
+            assert!(seg.generation <= MAX_GENERATION);
+            // Deeper generations stop shorter
+            let decay = props.branch_decay.powi(seg.generation as i32);
+            let max_len = (props.min_segment * SEGMENT_SPAN * decay).max(props.min_segment);
+            assert!(seg.start.distance(seg.end) < max_len + 1.0 + 1e-3);
+            let Some(parent) = seg.parent.map(|p| &segments[p as usize]) else {
+                continue;
+            };
+            // Children sprout from their parent's path
+            assert!(distance_to_segment(seg.start, parent.start, parent.end) < 1e-3);
+            let turn = parent.dir.dot(seg.dir).clamp(-1.0, 1.0).acos();
+            if seg.generation == parent.generation + 1 {
+                assert!((turn - props.branch_angle).abs() < 1e-3, "{turn}");
+                assert!(seg.width[0] < parent.width[1]);
+            } else {
+                // Continuations pick up where a finished tip stopped
+                assert_eq!(seg.generation, parent.generation);
+                assert!(turn <= TIP_JITTER + 1e-3, "{turn}");
+                assert!(!parent.growing && seg.start == parent.end);
+            }
+        }
+    }
+
+    #[test]
+    fn finished_segments_stop_growing() {
+        let mut brush = CrystalBrush::new();
+        let mut strokes = [seed_stroke()];
+        let mut rng = simulation::rng_from(3);
+        for _ in 0..200 {
+            brush.growth_step(&mut strokes, 2.0, None, 0.0, &mut rng);
+        }
+        let stopped: Vec<(usize, Pos2)> = strokes[0]
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !s.growing)
+            .map(|(i, s)| (i, s.end))
+            .collect();
+        assert!(!stopped.is_empty());
+
+        for _ in 0..50 {
+            brush.growth_step(&mut strokes, 2.0, None, 0.0, &mut rng);
+        }
+        for (i, end) in stopped {
+            assert_eq!(strokes[0].segments[i].end, end);
+        }
+    }
+
+    /// Grow `strokes` in Contain mode until nothing grows, without branching.
+    fn grow_contained(strokes: &mut [StrokeData], blots: &[Blot], bounds: Rect) {
+        let mut brush = CrystalBrush::new();
+        brush.props.min_segment = 1000.0;
+        let mut index = SceneIndex::new();
+        index.sync(strokes, blots);
+        let contain = Containment {
+            bounds,
+            blots,
+            index: &index,
+        };
+        let mut rng = simulation::rng_from(0);
+        for _ in 0..200 {
+            brush.growth_step(strokes, 2.0, Some(&contain), 0.0, &mut rng);
+        }
+        assert!(strokes.iter().flat_map(|s| &s.segments).all(|s| !s.growing));
+    }
+
+    /// A stroke growing along +x from (`x`, `y`).
+    fn tip(x: f32, y: f32) -> StrokeData {
+        let mut stroke = StrokeData::new(Color32::WHITE, true);
+        stroke.add_segment(Pos2::new(x, y), Pos2::new(x + 1.0, y), Vec2::X, [1.0; 2], 0.0);
+        stroke
+    }
+
+    #[test]
+    fn contained_tips_stop_at_the_bounds() {
+        let mut strokes = [tip(10.0, 20.0)];
+        let bounds = Rect::from_min_max(Pos2::ZERO, Pos2::new(50.0, 50.0));
+        grow_contained(&mut strokes, &[], bounds);
+        assert_eq!(strokes[0].segments[0].end, Pos2::new(50.0, 20.0));
+    }
+
+    #[test]
+    fn contained_tips_stop_at_other_strokes_and_blots() {
+        let bounds = Rect::from_min_max(Pos2::ZERO, Pos2::new(100.0, 100.0));
+        let mut wall = StrokeData::new(Color32::WHITE, false);
+        wall.add_segment(Pos2::new(30.0, 0.0), Pos2::new(30.0, 40.0), Vec2::Y, [1.0; 2], 0.0);
+        wall.segments[0].growing = false;
+        let mut strokes = [tip(10.0, 20.0), wall];
+        grow_contained(&mut strokes, &[], bounds);
+        assert!(strokes[0].segments[0].end.distance(Pos2::new(30.0, 20.0)) < 1e-4);
+
+        let blot = Blot {
+            pos: Pos2::new(30.0, 20.0),
+            radius: 5.0,
+            color: Color32::WHITE,
+            softness: 0.0,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        };
+        let mut strokes = [tip(10.0, 20.0)];
+        grow_contained(&mut strokes, &[blot], bounds);
+        // Stopped short of the blot's edge, less than one step away
+        let x = strokes[0].segments[0].end.x;
+        assert!((24.0..=25.0).contains(&x), "{x}");
     }
 }
@@ -1,11 +1,63 @@
 // app/brushes/crystal_props.rs
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+/// Pointer speed (points/s) treated as full speed by `speed_width`.
+const FULL_SPEED: f32 = 1500.0;
+
+/// Width multiple at the very end of a taper.
+const TAPER_TIP: f32 = 0.15;
+
+/// Curve fitted through a crystal stroke's path when it is released.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Smoothing {
+    None,
+    #[default]
+    Chaikin,
+    CatmullRom,
+}
+
+impl Smoothing {
+    pub const ALL: [Smoothing; 3] = [Smoothing::None, Smoothing::Chaikin, Smoothing::CatmullRom];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Smoothing::None => "None",
+            Smoothing::Chaikin => "Chaikin",
+            Smoothing::CatmullRom => "Catmull-Rom",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CrystalProps {
     pub branch_angle: f32,
     pub branch_decay: f32,
     pub min_segment: f32,
     pub thickness: f32,
+    /// How fast segments fade while decaying; older segments fade faster.
+    /// 0 disables fading, so decay only retracts.
+    pub decay_fade: f32,
+    /// Pointer positions averaged into each path point; 1 disables.
+    pub input_average: u32,
+    /// Lazy-mouse string length (points): the path only follows once the
+    /// pointer pulls the string taut. 0 disables.
+    pub lazy_radius: f32,
+    pub smoothing: Smoothing,
+    /// Respace the path to `min_segment` before building segments.
+    pub resample: bool,
+    /// Distance (points) over which a stroke widens from its start.
+    pub taper_start: f32,
+    /// Distance (points) over which a stroke narrows towards its end.
+    pub taper_end: f32,
+    /// Width of a side branch relative to the tip it sprouts from, so each
+    /// generation is thinner than the last.
+    pub branch_width: f32,
+    /// How much light pen pressure thins the stroke (0 = ignore pressure).
+    pub pressure_width: f32,
+    /// Width change at full pointer speed: -1 = vanishes, 0 = none, 1 = doubles.
+    pub speed_width: f32,
 }
 
 impl Default for CrystalProps {
@@ -15,6 +67,39 @@ impl Default for CrystalProps {
             branch_decay: 0.7,
             min_segment: 6.0,
             thickness: 2.0,
+            decay_fade: 1.0,
+            input_average: 4,
+            lazy_radius: 0.0,
+            smoothing: Smoothing::Chaikin,
+            resample: true,
+            taper_start: 24.0,
+            taper_end: 0.0,
+            branch_width: 0.7,
+            pressure_width: 0.5,
+            speed_width: 0.0,
         }
     }
 }
+
+impl CrystalProps {
+    /// Width multiple from pointer `speed` (points/s) and pen `pressure`
+    /// (0–1, `None` when the device doesn't report force).
+    pub fn input_width(&self, speed: f32, pressure: Option<f32>) -> f32 {
+        let speed_t = (speed / FULL_SPEED).clamp(0.0, 1.0);
+        let pressure_t = pressure.map_or(1.0, |p| p.clamp(0.0, 1.0));
+        (1.0 + self.speed_width * speed_t) * (1.0 - self.pressure_width * (1.0 - pressure_t))
+    }
+
+    /// Width multiple from the end tapers at distance `along` a stroke of
+    /// length `total`.
+    pub fn taper(&self, along: f32, total: f32) -> f32 {
+        let ramp = |dist: f32, len: f32| {
+            if len <= 0.0 {
+                return 1.0;
+            }
+            let t = (dist / len).clamp(0.0, 1.0);
+            TAPER_TIP + (1.0 - TAPER_TIP) * t * (2.0 - t)
+        };
+        ramp(along, self.taper_start) * ramp(total - along, self.taper_end)
+    }
+}
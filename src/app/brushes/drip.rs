@@ -1,46 +1,267 @@
 // app/brushes/drip.rs
-use eframe::egui::{Painter, Pos2, Stroke, Color32};
+use std::any::Any;
+
+use eframe::egui::{Pos2, Color32, Ui, Vec2};
+use rand::Rng;
+use crate::app::simulation;
+use serde::{Deserialize, Serialize};
 use crate::app::brushes::drip_props::DripProps;
-use crate::app::brushes::BrushEngine;
+use crate::app::ui::brush_props;
+use crate::app::brushes::{BrushContext, BrushEngine, BrushKind, CanvasElement, CanvasMut, TickContext};
+
+/// Average distance along the drawn path between paint runs.
+const RUN_SPACING: f32 = 14.0;
+
+/// Paint carried by a run, as trail length per unit of brush thickness.
+const PAINT_PER_THICKNESS: (f32, f32) = (8.0, 40.0);
+
+/// Gravity is given per frame in props; this converts it to points/s².
+const GRAVITY_SCALE: f32 = 60.0;
+
+/// Runs slower than this (points/s) have dried in place.
+const MIN_VELOCITY: f32 = 0.5;
+
+/// New trail points are recorded every this many points of travel.
+const TRAIL_STEP: f32 = 2.0;
+
+/// Width at the end of a trail relative to its start.
+const TAPER_END: f32 = 0.3;
+
+/// Diameter of the bead at a run's head relative to the stroke width.
+const HEAD_BEAD: f32 = 0.7;
+
+/// One run of paint flowing down from a point on a drip stroke.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DripRun {
+    /// Trail from the origin on the stroke down to the head (last point).
+    pub points: Vec<Pos2>,
+    /// Downward speed in points per second.
+    pub velocity: f32,
+    /// Trail length still available before the run runs dry.
+    pub paint: f32,
+    pub flowing: bool,
+}
+
+impl DripRun {
+    pub fn head(&self) -> Pos2 {
+        self.points.last().copied().unwrap_or(Pos2::ZERO)
+    }
+
+    /// Trail pieces with their tapered widths, from origin to head.
+    pub fn pieces(&self, width: f32) -> impl Iterator<Item = (Pos2, Pos2, f32)> + '_ {
+        let n = self.points.len().saturating_sub(1).max(1) as f32;
+        self.points.windows(2).enumerate().map(move |(i, w)| {
+            let t = (i as f32 + 0.5) / n;
+            (w[0], w[1], width * (1.0 - t * (1.0 - TAPER_END)))
+        })
+    }
+
+    /// Radius of the bead of paint gathered at the head of the run.
+    pub fn head_radius(&self, width: f32) -> f32 {
+        if self.points.len() < 2 {
+            0.0
+        } else {
+            width * 0.5 * HEAD_BEAD
+        }
+    }
+}
 
-/// Simple drip brush: draws a falling, gravity-affected line segment.
+/// A committed drip stroke: the drawn path plus the paint running off it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Drip {
+    pub path: Vec<Pos2>,
+    pub color: Color32,
+    pub thickness: f32,
+    pub runs: Vec<DripRun>,
+    /// Brush gravity and viscosity when the drip was made; its runs keep
+    /// flowing by them whatever the brush is set to later.
+    #[serde(default = "default_gravity")]
+    pub gravity: f32,
+    #[serde(default = "default_viscosity")]
+    pub viscosity: f32,
+}
+
+fn default_gravity() -> f32 {
+    DripProps::default().gravity
+}
+
+fn default_viscosity() -> f32 {
+    DripProps::default().viscosity
+}
+
+/// Drip brush: strokes shed runs of paint that flow downward under gravity.
 pub struct DripBrush {
     pub props: DripProps,
-    velocity: f32,
+
+    /// Pointer path of the stroke being drawn.
+    points: Vec<Pos2>,
 }
 
 impl DripBrush {
     pub fn new() -> Self {
         Self {
             props: DripProps::default(),
-            velocity: 0.0,
+            points: Vec::new(),
+        }
+    }
+
+    /// Turn a finished pointer path into a drip with runs seeded along it.
+    pub fn make_drip(&self, path: &[Pos2], color: Color32, seed: u64) -> Drip {
+        let mut rng = simulation::rng_from(seed);
+        let mut runs = Vec::new();
+
+        let mut next_run = rng.random_range(0.0..RUN_SPACING);
+        let mut travelled = 0.0;
+
+        for w in path.windows(2) {
+            let (a, b) = (w[0], w[1]);
+            let len = a.distance(b);
+
+            while travelled + len >= next_run {
+                let t = if len > 0.0 { (next_run - travelled) / len } else { 0.0 };
+                let origin = a + (b - a) * t;
+                let (lo, hi) = PAINT_PER_THICKNESS;
+
+                runs.push(DripRun {
+                    points: vec![origin],
+                    velocity: 0.0,
+                    paint: rng.random_range(lo..hi) * self.props.thickness,
+                    flowing: true,
+                });
+
+                next_run += rng.random_range(RUN_SPACING * 0.5..RUN_SPACING * 1.5);
+            }
+
+            travelled += len;
+        }
+
+        Drip {
+            path: path.to_vec(),
+            color,
+            thickness: self.props.thickness,
+            runs,
+            gravity: self.props.gravity,
+            viscosity: self.props.viscosity,
         }
     }
 
-    /// Optional per-frame physics update (currently unused).
-    pub fn tick(&mut self) {
-        // Placeholder for future drip physics.
-        // We keep this for extensibility.
+    /// Per-frame physics: runs accelerate under their drip's `gravity`, are
+    /// damped by its `viscosity` and stop once their paint is used up or they
+    /// stall.
+    pub fn flow(drips: &mut [Drip], dt: f32) {
+        for drip in drips.iter_mut() {
+            let accel = drip.gravity * GRAVITY_SCALE;
+            let damping = drip.viscosity.clamp(0.0, 1.0).powf(dt * 60.0);
+            for run in &mut drip.runs {
+                Self::flow_run(run, accel, damping, dt);
+            }
+        }
+    }
+
+    fn flow_run(run: &mut DripRun, accel: f32, damping: f32, dt: f32) {
+        if !run.flowing || run.points.is_empty() {
+            return;
+        }
+
+        run.velocity = (run.velocity + accel * dt) * damping;
+
+        let fall = (run.velocity * dt).min(run.paint);
+        run.paint -= fall;
+
+        let head = run.head() + Vec2::new(0.0, fall);
+        let last_fixed = run.points[run.points.len().saturating_sub(2)];
+        if run.points.len() < 2 || last_fixed.distance(head) >= TRAIL_STEP {
+            run.points.push(head);
+        } else if let Some(last) = run.points.last_mut() {
+            *last = head;
+        }
+
+        // Once moving, a run that slows to a crawl has dried.
+        let stalled = run.velocity < MIN_VELOCITY && run.points.len() > 2;
+        if run.paint <= 0.0 || stalled {
+            run.flowing = false;
+        }
     }
 }
 
 impl BrushEngine for DripBrush {
-    fn stroke(&mut self, painter: &Painter, from: Pos2, mut to: Pos2, color: Color32) {
-        // Gravity increases downward velocity each stroke step.
-        self.velocity += self.props.gravity * 0.02;
+    fn kind(&self) -> BrushKind {
+        BrushKind::DRIP
+    }
 
-        // Viscosity dampens motion to avoid extreme acceleration.
-        self.velocity *= self.props.viscosity;
+    fn label(&self) -> &'static str {
+        "Drip"
+    }
 
-        // Apply the vertical drift.
-        to.y += self.velocity;
+    fn begin(&mut self, _ctx: &BrushContext, _pos: Pos2) -> Vec<CanvasElement> {
+        self.points.clear();
+        Vec::new()
+    }
 
-        // Paint the drip segment
-        painter.line_segment([from, to], Stroke::new(self.props.thickness, color));
+    fn drag(&mut self, _ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        if self.points.last() != Some(&pos) {
+            self.points.push(pos);
+        }
+        Vec::new()
+    }
+
+    fn end(&mut self, ctx: &BrushContext) -> Vec<CanvasElement> {
+        let points = std::mem::take(&mut self.points);
+        if points.len() < 2 {
+            return Vec::new();
+        }
+        vec![CanvasElement::Drip(self.make_drip(&points, ctx.color, ctx.seed))]
     }
 
-    fn finish_stroke(&mut self) {
-        // Reset velocity so the next stroke starts fresh.
-        self.velocity = 0.0;
+    fn tick(&mut self, canvas: &mut CanvasMut, ctx: &TickContext) -> bool {
+        Self::flow(canvas.drips, ctx.dt);
+        false
+    }
+
+    fn preview(&self) -> &[Pos2] {
+        &self.points
+    }
+
+    fn properties_ui(&mut self, ui: &mut Ui, color: Color32) {
+        brush_props::drip(ui, &mut self.props, color);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lowest point reached by any of the drip's runs.
+    fn lowest(drip: &Drip) -> f32 {
+        drip.runs.iter().map(|r| r.head().y).fold(f32::MIN, f32::max)
+    }
+
+    #[test]
+    fn drips_flow_by_the_props_they_were_made_with() {
+        let path = [Pos2::new(0.0, 0.0), Pos2::new(60.0, 0.0)];
+        let mut brush = DripBrush::new();
+        let made = brush.make_drip(&path, Color32::WHITE, 3);
+        let mut drips = vec![made.clone()];
+
+        // Later drips don't run at all
+        brush.props.gravity = 0.0;
+        drips.push(brush.make_drip(&path, Color32::WHITE, 3));
+        let mut alone = vec![made];
+        for _ in 0..60 {
+            DripBrush::flow(&mut drips, 1.0 / 60.0);
+            DripBrush::flow(&mut alone, 1.0 / 60.0);
+        }
+
+        assert!(lowest(&drips[0]) > 1.0);
+        assert_eq!(lowest(&drips[0]), lowest(&alone[0]));
+        assert_eq!(lowest(&drips[1]), 0.0);
     }
 }
@@ -1,6 +1,9 @@
 // app/brushes/drip_props.rs
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DripProps {
     pub gravity: f32,
     pub viscosity: f32,
@@ -0,0 +1,427 @@
+// app/brushes/eraser.rs
+
+use std::any::Any;
+
+use eframe::egui::{Color32, Pos2, Rect, Ui};
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::crystal::{Segment, StrokeData};
+use crate::app::brushes::eraser_props::EraserProps;
+use crate::app::brushes::{BrushContext, BrushEngine, BrushKind, CanvasElement, LayerEdit};
+use crate::app::history::Operation;
+use crate::app::ui::brush_props;
+use crate::app::utils::math::{capsule_overlap, distance_to_segment};
+
+/// Pieces shorter than this (in points) left over by a cut are dropped.
+const MIN_PIECE: f32 = 0.5;
+
+/// Blots shrunk below this radius are removed.
+const MIN_BLOT_RADIUS: f32 = 0.5;
+
+/// One stretch of eraser movement: everything within `radius` of the
+/// segment `from`–`to` is removed. Drips are left alone.
+#[derive(Clone, Copy, Debug)]
+pub struct EraseCut {
+    pub from: Pos2,
+    pub to: Pos2,
+    pub radius: f32,
+    pub strokes: bool,
+    pub blots: bool,
+}
+
+impl EraseCut {
+    /// Bounding box of the erased area.
+    fn bounds(&self) -> Rect {
+        Rect::from_two_pos(self.from, self.to).expand(self.radius)
+    }
+}
+
+/// Cut every stroke crossing `cut`. A cut stroke is replaced, in place, by
+/// its surviving pieces, one stroke per connected part. Returns `true` if
+/// anything changed.
+pub fn erase_strokes(strokes: &mut Vec<StrokeData>, cut: &EraseCut) -> bool {
+    let mut changed = false;
+    let mut out = Vec::with_capacity(strokes.len());
+    for stroke in strokes.drain(..) {
+        match cut_stroke(&stroke, cut) {
+            Some(pieces) => {
+                changed = true;
+                out.extend(pieces);
+            }
+            None => out.push(stroke),
+        }
+    }
+    *strokes = out;
+    changed
+}
+
+/// Remove blots whose center `cut` covers and shrink the ones it overlaps
+/// until they clear it. Returns `true` if anything changed.
+pub fn erase_blots(blots: &mut Vec<Blot>, cut: &EraseCut) -> bool {
+    let mut changed = false;
+    blots.retain_mut(|b| {
+        let dist = distance_to_segment(b.pos, cut.from, cut.to);
+        if dist >= cut.radius + b.radius {
+            return true;
+        }
+        changed = true;
+        b.radius = dist - cut.radius;
+        b.radius >= MIN_BLOT_RADIUS
+    });
+    changed
+}
+
+/// Remove the part of `stroke` inside `cut`. Returns `None` if the cut
+/// misses it, otherwise the remaining connected parts as separate strokes.
+///
+/// A cut segment leaves a head piece (before the cut, keeping its parent)
+/// and/or a tail piece (after it, starting a new part). Children re-attach
+/// to whichever piece they sprouted from, or start new parts if their
+/// attachment point was erased.
+pub fn cut_stroke(stroke: &StrokeData, cut: &EraseCut) -> Option<Vec<StrokeData>> {
+    let area = cut.bounds();
+    let spans: Vec<Option<(f32, f32)>> = stroke
+        .segments
+        .iter()
+        .map(|s| {
+            if !area.intersects(Rect::from_two_pos(s.start, s.end)) {
+                return None;
+            }
+            capsule_overlap(s.start, s.end, cut.from, cut.to, cut.radius)
+        })
+        .collect();
+    if spans.iter().all(Option::is_none) {
+        return None;
+    }
+
+    // Pieces in original order, so parents still precede their children;
+    // `head[i]`/`tail[i]` are the pieces holding the start/end of segment i.
+    let n = stroke.segments.len();
+    let mut pieces: Vec<Segment> = Vec::new();
+    let mut head = vec![None; n];
+    let mut tail = vec![None; n];
+    for (i, seg) in stroke.segments.iter().enumerate() {
+        let parent = seg.parent.and_then(|p| {
+            let p = p as usize;
+            let parent = stroke.segments.get(p)?;
+            let t = param_on(seg.start, parent.start, parent.end);
+            match spans[p] {
+                None => head[p],
+                Some((t0, _)) if t <= t0 => head[p],
+                Some((_, t1)) if t >= t1 => tail[p],
+                Some(_) => None,
+            }
+        });
+
+        let Some((t0, t1)) = spans[i] else {
+            pieces.push(Segment { parent, ..seg.clone() });
+            head[i] = Some(pieces.len() as u32 - 1);
+            tail[i] = head[i];
+            continue;
+        };
+
+        let span = seg.end - seg.start;
+        let len = span.length();
+        if len * t0 > MIN_PIECE {
+            pieces.push(Segment {
+                end: seg.start + span * t0,
+                growing: false,
+                parent,
+                width: [seg.width[0], seg.width_at(t0)],
+                ..seg.clone()
+            });
+            head[i] = Some(pieces.len() as u32 - 1);
+        }
+        if len * (1.0 - t1) > MIN_PIECE {
+            pieces.push(Segment {
+                start: seg.start + span * t1,
+                parent: None,
+                width: [seg.width_at(t1), seg.width[1]],
+                ..seg.clone()
+            });
+            tail[i] = Some(pieces.len() as u32 - 1);
+        }
+    }
+
+    // Split into connected parts; roots start a part, children join theirs
+    let mut part = Vec::with_capacity(pieces.len());
+    let mut local = Vec::with_capacity(pieces.len());
+    let mut parts: Vec<StrokeData> = Vec::new();
+    for seg in pieces {
+        let p = match seg.parent {
+            Some(parent) => part[parent as usize],
+            None => {
+                parts.push(StrokeData {
+                    segments: Vec::new(),
+                    color: stroke.color,
+                    thickness: stroke.thickness,
+                });
+                parts.len() - 1
+            }
+        };
+        let segments = &mut parts[p].segments;
+        let parent = seg.parent.map(|parent| local[parent as usize]);
+        part.push(p);
+        local.push(segments.len() as u32);
+        segments.push(Segment { parent, ..seg });
+    }
+
+    Some(parts)
+}
+
+/// Position of `p` projected onto `a`–`b`, as a fraction of its length.
+fn param_on(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_sq();
+    if len_sq <= f32::EPSILON {
+        return 0.0;
+    }
+    ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0)
+}
+
+/// Eraser: removes crystal segments and blots along the pointer path.
+pub struct EraserBrush {
+    pub props: EraserProps,
+
+    /// Pointer path of the gesture in progress.
+    points: Vec<Pos2>,
+
+    /// Cuts not yet applied to the layer.
+    cuts: Vec<EraseCut>,
+
+    /// Strokes and blots of the layer before the gesture first cut them.
+    before: Option<(Vec<StrokeData>, Vec<Blot>)>,
+}
+
+impl EraserBrush {
+    pub fn new() -> Self {
+        Self {
+            props: EraserProps::default(),
+            points: Vec::new(),
+            cuts: Vec::new(),
+            before: None,
+        }
+    }
+
+    fn cut(&mut self, from: Pos2, to: Pos2) {
+        self.cuts.push(EraseCut {
+            from,
+            to,
+            radius: self.props.radius.max(0.5),
+            strokes: self.props.strokes,
+            blots: self.props.blots,
+        });
+    }
+}
+
+impl BrushEngine for EraserBrush {
+    fn kind(&self) -> BrushKind {
+        BrushKind::ERASER
+    }
+
+    fn label(&self) -> &'static str {
+        "Eraser"
+    }
+
+    fn begin(&mut self, _ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        self.points.clear();
+        self.points.push(pos);
+        self.cuts.clear();
+        self.before = None;
+        self.cut(pos, pos);
+        Vec::new()
+    }
+
+    fn drag(&mut self, _ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        let Some(&last) = self.points.last() else {
+            return Vec::new();
+        };
+        if last == pos {
+            return Vec::new();
+        }
+        self.points.push(pos);
+        self.cut(last, pos);
+        Vec::new()
+    }
+
+    fn end(&mut self, _ctx: &BrushContext) -> Vec<CanvasElement> {
+        self.points.clear();
+        Vec::new()
+    }
+
+    /// Apply the pending cuts; everything erased during the gesture becomes
+    /// one undo step when it ends.
+    fn edit(&mut self, edit: &mut LayerEdit) {
+        let layer = &mut *edit.layer;
+        for cut in self.cuts.drain(..) {
+            let before = self
+                .before
+                .is_none()
+                .then(|| (layer.strokes.clone(), layer.blots.clone()));
+            let mut changed = false;
+            if cut.strokes {
+                changed |= erase_strokes(&mut layer.strokes, &cut);
+            }
+            if cut.blots {
+                changed |= erase_blots(&mut layer.blots, &cut);
+            }
+            if changed {
+                if before.is_some() {
+                    self.before = before;
+                }
+                layer.index.invalidate();
+                *edit.selection = None;
+            }
+        }
+
+        if !edit.finished {
+            return;
+        }
+        if let Some((strokes_before, blots_before)) = self.before.take() {
+            edit.history.push(Operation::Edited {
+                layer: layer.id,
+                strokes_before,
+                blots_before,
+                strokes_after: layer.strokes.clone(),
+                blots_after: layer.blots.clone(),
+                drips_start: 0,
+                drips_added: Vec::new(),
+            });
+        }
+    }
+
+    fn preview(&self) -> &[Pos2] {
+        &self.points
+    }
+
+    fn properties_ui(&mut self, ui: &mut Ui, color: Color32) {
+        brush_props::eraser(ui, &mut self.props, color);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Vec2;
+
+    use crate::app::blend::BlendMode;
+    use crate::app::state::AppState;
+
+    /// Ten 10-point segments along the x axis from 0 to 100.
+    fn line() -> StrokeData {
+        let mut stroke = StrokeData::new(Color32::WHITE, false);
+        for i in 0..10 {
+            let x = i as f32 * 10.0;
+            stroke.add_segment(Pos2::new(x, 0.0), Pos2::new(x + 10.0, 0.0), Vec2::X, [1.0; 2], 0.0);
+        }
+        stroke
+    }
+
+    fn blot(x: f32, radius: f32) -> Blot {
+        Blot {
+            pos: Pos2::new(x, 0.0),
+            radius,
+            color: Color32::WHITE,
+            softness: 0.0,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    /// A vertical cut through `x`.
+    fn cut_at(x: f32, radius: f32) -> EraseCut {
+        EraseCut {
+            from: Pos2::new(x, -20.0),
+            to: Pos2::new(x, 20.0),
+            radius,
+            strokes: true,
+            blots: true,
+        }
+    }
+
+    fn extent(stroke: &StrokeData) -> (f32, f32) {
+        let xs = stroke.segments.iter().flat_map(|s| [s.start.x, s.end.x]);
+        xs.fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)))
+    }
+
+    #[test]
+    fn a_middle_cut_splits_the_stroke() {
+        let pieces = cut_stroke(&line(), &cut_at(45.0, 5.0)).expect("the cut hits");
+        assert_eq!(pieces.len(), 2);
+        let (a, b) = (extent(&pieces[0]), extent(&pieces[1]));
+        assert!((a.0 - 0.0).abs() < 1e-3 && (a.1 - 40.0).abs() < 1e-3, "{a:?}");
+        assert!((b.0 - 50.0).abs() < 1e-3 && (b.1 - 100.0).abs() < 1e-3, "{b:?}");
+        for piece in &pieces {
+            assert_eq!(piece.segments[0].parent, None);
+            assert!(piece.segments[1..].iter().all(|s| s.parent.is_some()));
+        }
+    }
+
+    #[test]
+    fn an_end_cut_shortens_the_stroke() {
+        let pieces = cut_stroke(&line(), &cut_at(100.0, 15.0)).expect("the cut hits");
+        assert_eq!(pieces.len(), 1);
+        let (lo, hi) = extent(&pieces[0]);
+        assert!(lo.abs() < 1e-3 && (hi - 85.0).abs() < 1e-3, "{lo}..{hi}");
+
+        assert!(cut_stroke(&line(), &cut_at(130.0, 15.0)).is_none());
+    }
+
+    #[test]
+    fn erasing_a_whole_stroke_removes_it() {
+        let mut strokes = vec![line()];
+        let cut = EraseCut {
+            from: Pos2::new(-10.0, 0.0),
+            to: Pos2::new(110.0, 0.0),
+            ..cut_at(0.0, 5.0)
+        };
+        assert!(erase_strokes(&mut strokes, &cut));
+        assert!(strokes.is_empty());
+    }
+
+    #[test]
+    fn blots_under_the_eraser_are_removed_or_shrunk() {
+        let mut blots = vec![blot(0.0, 4.0), blot(14.0, 6.0), blot(40.0, 4.0)];
+        assert!(erase_blots(&mut blots, &cut_at(0.0, 10.0)));
+
+        // The covered blot is gone, the grazed one clears the capsule
+        assert_eq!(blots.len(), 2);
+        assert!((blots[0].radius - 4.0).abs() < 1e-4);
+        assert_eq!(blots[1].radius, 4.0);
+        assert!(!erase_blots(&mut blots, &cut_at(0.0, 10.0)));
+    }
+
+    #[test]
+    fn one_undo_restores_the_erased_layer() {
+        let mut state = AppState::without_user_config();
+        {
+            let layer = &mut state.layers[0];
+            layer.strokes.push(line());
+            layer.blots.extend([blot(30.0, 4.0), blot(60.0, 4.0)]);
+        }
+        let snapshot = |state: &AppState| {
+            let layer = state.active_layer();
+            serde_json::to_string(&(&layer.strokes, &layer.blots)).unwrap()
+        };
+        let before = snapshot(&state);
+
+        state.active_brush = BrushKind::ERASER;
+        state.pointer_down(Pos2::new(30.0, -20.0));
+        state.pointer_drag(Pos2::new(30.0, 20.0));
+        state.pointer_drag(Pos2::new(60.0, 20.0));
+        state.pointer_drag(Pos2::new(60.0, -20.0));
+        state.pointer_up();
+        assert_eq!(state.active_layer().strokes.len(), 3);
+        assert!(state.active_layer().blots.is_empty());
+
+        state.undo();
+        assert_eq!(snapshot(&state), before);
+    }
+}
@@ -0,0 +1,24 @@
+// app/brushes/eraser_props.rs
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EraserProps {
+    /// Half the width of the erased path.
+    pub radius: f32,
+    /// Cut crystal strokes.
+    pub strokes: bool,
+    /// Remove blots under the eraser and shrink the ones it grazes.
+    pub blots: bool,
+}
+
+impl Default for EraserProps {
+    fn default() -> Self {
+        Self {
+            radius: 12.0,
+            strokes: true,
+            blots: true,
+        }
+    }
+}
@@ -6,26 +6,136 @@ pub mod drip;
 pub mod drip_props;
 pub mod blotter;
 pub mod blotter_props;
+pub mod eraser;
+pub mod eraser_props;
+pub mod select;
+pub mod select_props;
+pub mod registry;
 
-use eframe::egui::{Color32, Painter, Pos2};
+use std::any::Any;
 
-/// All brush types available in the app.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum BrushKind {
-    Crystal,
-    Drip,
-    Blotter,
+use eframe::egui::{Color32, Pos2, Rect, Ui};
+
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::crystal::StrokeData;
+use crate::app::brushes::drip::Drip;
+use crate::app::history::History;
+use crate::app::layers::Layer;
+use crate::app::selection::Selection;
+use crate::app::spatial::SceneIndex;
+
+/// Identifier a brush is registered under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BrushKind(pub &'static str);
+
+impl BrushKind {
+    pub const CRYSTAL: Self = Self("crystal");
+    pub const DRIP: Self = Self("drip");
+    pub const BLOTTER: Self = Self("blotter");
+    pub const ERASER: Self = Self("eraser");
+    pub const SELECT: Self = Self("select");
+}
+
+/// A piece of artwork produced by a brush, ready to be stored on the canvas.
+pub enum CanvasElement {
+    Stroke(StrokeData),
+    Drip(Drip),
+    Blots(Vec<Blot>),
+}
+
+/// Per-gesture input handed to brush hooks.
+pub struct BrushContext {
+    /// User-selected paint color.
+    pub color: Color32,
+    /// Canvas clock, for stamping new elements.
+    pub time: f64,
+    /// Pen/touch force in 0–1, when the input device reports it.
+    pub pressure: Option<f32>,
+    /// Seed for any randomness in this event, see `simulation::rng_from`.
+    pub seed: u64,
+}
+
+/// Per-frame simulation settings handed to `BrushEngine::tick`.
+pub struct TickContext {
+    /// Length of one simulation step in seconds, see `simulation::TIMESTEP`.
+    pub dt: f32,
+    /// Canvas clock.
+    pub time: f64,
+    /// Crystal growth speed for this frame, or `None` when growth doesn't step.
+    pub growth: Option<f32>,
+    /// Canvas rectangle when "Contain" is on.
+    pub contain: Option<Rect>,
+    /// Seed for any randomness in this step, see `simulation::rng_from`.
+    pub seed: u64,
+}
+
+/// Mutable view of the stored canvas elements that brushes simulate.
+pub struct CanvasMut<'a> {
+    pub strokes: &'a mut [StrokeData],
+    pub drips: &'a mut [Drip],
+    pub blots: &'a [Blot],
+    pub index: &'a SceneIndex,
+}
+
+/// The gesture's layer and the state tools edit along with it, handed to
+/// `BrushEngine::edit`.
+pub struct LayerEdit<'a> {
+    pub layer: &'a mut Layer,
+    pub selection: &'a mut Option<Selection>,
+    /// Undo log; a tool records its own changes to the layer.
+    pub history: &'a mut History,
+    /// Screen pixels per world unit, for hit-testing on-screen handles.
+    pub zoom: f32,
+    /// Set on the call after `end`.
+    pub finished: bool,
 }
 
 /// Shared interface for all brushes.
 ///
-/// Each brush receives:
-/// - `painter`: the egui painter
-/// - `from` / `to`: pointer segment positions
-/// - `color`: user-selected paint color
-pub trait BrushEngine {
-    fn stroke(&mut self, painter: &Painter, from: Pos2, to: Pos2, color: Color32);
-
-    /// Optional callback invoked when the pointer is released.
-    fn finish_stroke(&mut self) {}
+/// A gesture is `begin`, any number of `drag`s and `end`; each hook returns
+/// the canvas elements it produced. Tools that change existing elements
+/// instead do so in `edit`. `tick` runs every frame for every
+/// registered brush so it can simulate the elements it owns.
+pub trait BrushEngine: Any {
+    fn kind(&self) -> BrushKind;
+
+    /// Human-readable name for menus and buttons.
+    fn label(&self) -> &'static str;
+
+    fn begin(&mut self, _ctx: &BrushContext, _pos: Pos2) -> Vec<CanvasElement> {
+        Vec::new()
+    }
+
+    fn drag(&mut self, ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement>;
+
+    fn end(&mut self, ctx: &BrushContext) -> Vec<CanvasElement>;
+
+    /// Edit the gesture's layer in place; called after `begin`, each `drag`
+    /// and `end`.
+    fn edit(&mut self, _edit: &mut LayerEdit) {}
+
+    /// Whether the selection is shown, and the selection and clipboard
+    /// commands act on it, while this tool is active.
+    fn uses_selection(&self) -> bool {
+        false
+    }
+
+    /// Per-frame simulation. Returns `true` when stored segments were removed
+    /// or moved; growth (segments appended, growing tips extending) is picked
+    /// up by the layer's index on its own.
+    fn tick(&mut self, _canvas: &mut CanvasMut, _ctx: &TickContext) -> bool {
+        false
+    }
+
+    /// Pointer path of the gesture in progress, drawn as a live preview.
+    fn preview(&self) -> &[Pos2] {
+        &[]
+    }
+
+    /// Contents of the "Properties" menu for this brush. `color` is the
+    /// current paint color, for tip previews.
+    fn properties_ui(&mut self, _ui: &mut Ui, _color: Color32) {}
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
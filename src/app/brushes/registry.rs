@@ -0,0 +1,67 @@
+// app/brushes/registry.rs
+use crate::app::brushes::{blotter, crystal, drip, eraser, select, BrushEngine, BrushKind};
+
+/// All available brushes, keyed by `BrushKind`, in menu order.
+pub struct BrushRegistry {
+    brushes: Vec<Box<dyn BrushEngine>>,
+}
+
+impl BrushRegistry {
+    /// Registry holding the built-in brushes.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self {
+            brushes: Vec::new(),
+        };
+        registry.register(Box::new(crystal::CrystalBrush::new()));
+        registry.register(Box::new(drip::DripBrush::new()));
+        registry.register(Box::new(blotter::Blotter::new()));
+        registry.register(Box::new(eraser::EraserBrush::new()));
+        registry.register(Box::new(select::SelectTool::new()));
+        registry
+    }
+
+    /// Add a brush, replacing any brush already registered under the same kind.
+    pub fn register(&mut self, brush: Box<dyn BrushEngine>) {
+        let kind = brush.kind();
+        match self.brushes.iter_mut().find(|b| b.kind() == kind) {
+            Some(slot) => *slot = brush,
+            None => self.brushes.push(brush),
+        }
+    }
+
+    pub fn get(&self, kind: BrushKind) -> Option<&dyn BrushEngine> {
+        self.brushes.iter().find(|b| b.kind() == kind).map(|b| b.as_ref())
+    }
+
+    pub fn get_mut(&mut self, kind: BrushKind) -> Option<&mut (dyn BrushEngine + 'static)> {
+        self.brushes
+            .iter_mut()
+            .find(|b| b.kind() == kind)
+            .map(|b| b.as_mut())
+    }
+
+    /// First registered brush of concrete type `T`.
+    pub fn typed<T: BrushEngine>(&self) -> Option<&T> {
+        self.brushes.iter().find_map(|b| b.as_any().downcast_ref::<T>())
+    }
+
+    pub fn typed_mut<T: BrushEngine>(&mut self) -> Option<&mut T> {
+        self.brushes
+            .iter_mut()
+            .find_map(|b| b.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Registered kind whose id is `id`, e.g. when reading one back from a file.
+    pub fn kind_by_id(&self, id: &str) -> Option<BrushKind> {
+        self.brushes.iter().map(|b| b.kind()).find(|k| k.0 == id)
+    }
+
+    /// `(kind, label)` of every brush, in registration order.
+    pub fn entries(&self) -> Vec<(BrushKind, &'static str)> {
+        self.brushes.iter().map(|b| (b.kind(), b.label())).collect()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn BrushEngine>> {
+        self.brushes.iter_mut()
+    }
+}
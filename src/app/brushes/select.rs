@@ -0,0 +1,204 @@
+// app/brushes/select.rs
+
+use std::any::Any;
+
+use eframe::egui::{Color32, Pos2, Rect, Ui};
+use crate::app::brushes::select_props::{MarqueeShape, SelectProps};
+use crate::app::brushes::{BrushContext, BrushEngine, BrushKind, CanvasElement, LayerEdit};
+use crate::app::history::Operation;
+use crate::app::selection::{self, Marquee, Selection, TransformDrag, HANDLE_SIZE};
+use crate::app::ui::brush_props;
+use crate::app::utils::math::Affine;
+
+/// Pointer input the next `edit` acts on.
+enum Step {
+    Press(Pos2),
+    Move(Pos2),
+}
+
+/// Selection tool. It produces no elements: a gesture starting on the
+/// selection's handles moves, scales or rotates it, any other gesture
+/// selects what the swept [`Marquee`] encloses once it ends.
+pub struct SelectTool {
+    pub props: SelectProps,
+
+    /// Pointer path of the gesture in progress.
+    path: Vec<Pos2>,
+
+    /// Marquee outline shown while dragging.
+    outline: Vec<Pos2>,
+
+    step: Option<Step>,
+
+    /// Transform in progress when the gesture grabbed a handle.
+    transform: Option<TransformDrag>,
+}
+
+impl SelectTool {
+    pub fn new() -> Self {
+        Self {
+            props: SelectProps::default(),
+            path: Vec::new(),
+            outline: Vec::new(),
+            step: None,
+            transform: None,
+        }
+    }
+
+    /// Area swept by the gesture in progress.
+    pub fn marquee(&self) -> Option<Marquee> {
+        let (&first, &last) = (self.path.first()?, self.path.last()?);
+        Some(match self.props.shape {
+            MarqueeShape::Rectangle => Marquee::Rect(Rect::from_two_pos(first, last)),
+            MarqueeShape::Lasso => Marquee::Lasso(self.path.clone()),
+        })
+    }
+
+    /// Replace the selection with what the marquee encloses on the layer; a
+    /// click picks the element under it instead.
+    fn select(&self, edit: &mut LayerEdit) {
+        let Some(marquee) = self.marquee() else {
+            return;
+        };
+        let layer = &mut *edit.layer;
+        let area = marquee.bounds();
+        let selection = if area.size().max_elem() * edit.zoom < HANDLE_SIZE {
+            layer.index.sync(&layer.strokes, &layer.blots);
+            Selection::at_point(layer, area.center(), HANDLE_SIZE / edit.zoom)
+        } else {
+            Selection::in_marquee(layer, &marquee)
+        };
+        *edit.selection = (!selection.is_empty()).then_some(selection);
+    }
+
+    /// Feed the pointer to the transform in progress, or start one if the
+    /// gesture pressed on a handle of the selection on this layer.
+    fn transform(&mut self, edit: &mut LayerEdit) {
+        let Some(step) = self.step.take() else {
+            return;
+        };
+        let Some(selection) = edit.selection.as_ref().filter(|s| s.layer == edit.layer.id)
+        else {
+            return;
+        };
+        match step {
+            Step::Press(pos) => {
+                let Some(bounds) = selection.bounds(edit.layer) else {
+                    return;
+                };
+                if let Some(handle) = selection::handle_at(bounds, pos, edit.zoom) {
+                    self.transform = Some(TransformDrag::new(handle, bounds, pos));
+                    self.outline.clear();
+                }
+            }
+            Step::Move(pos) => {
+                if let Some(drag) = &mut self.transform {
+                    let step = drag.step(pos);
+                    selection.transform(edit.layer, &step);
+                }
+            }
+        }
+    }
+
+    fn update_outline(&mut self) {
+        self.outline.clear();
+        let (Some(&first), Some(&last)) = (self.path.first(), self.path.last()) else {
+            return;
+        };
+        match self.props.shape {
+            MarqueeShape::Rectangle => {
+                let rect = Rect::from_two_pos(first, last);
+                self.outline.extend([
+                    rect.left_top(),
+                    rect.right_top(),
+                    rect.right_bottom(),
+                    rect.left_bottom(),
+                    rect.left_top(),
+                ]);
+            }
+            MarqueeShape::Lasso => {
+                self.outline.extend_from_slice(&self.path);
+                self.outline.push(first);
+            }
+        }
+    }
+}
+
+impl BrushEngine for SelectTool {
+    fn kind(&self) -> BrushKind {
+        BrushKind::SELECT
+    }
+
+    fn label(&self) -> &'static str {
+        "Select"
+    }
+
+    fn begin(&mut self, _ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        self.path.clear();
+        self.path.push(pos);
+        self.update_outline();
+        self.step = Some(Step::Press(pos));
+        self.transform = None;
+        Vec::new()
+    }
+
+    fn drag(&mut self, _ctx: &BrushContext, pos: Pos2) -> Vec<CanvasElement> {
+        if self.path.last() != Some(&pos) {
+            self.path.push(pos);
+            if self.transform.is_none() {
+                self.update_outline();
+            }
+            self.step = Some(Step::Move(pos));
+        }
+        Vec::new()
+    }
+
+    fn end(&mut self, _ctx: &BrushContext) -> Vec<CanvasElement> {
+        Vec::new()
+    }
+
+    fn edit(&mut self, edit: &mut LayerEdit) {
+        self.transform(edit);
+        if !edit.finished {
+            return;
+        }
+        match self.transform.take() {
+            Some(drag) => {
+                if let Some(selection) = edit.selection.as_ref() {
+                    if drag.total != Affine::IDENTITY {
+                        edit.history.push(Operation::Transformed {
+                            layer: selection.layer,
+                            strokes: selection.strokes.clone(),
+                            blots: selection.blots.clone(),
+                            transform: drag.total,
+                        });
+                    }
+                }
+            }
+            None => self.select(edit),
+        }
+        self.path.clear();
+        self.outline.clear();
+        self.step = None;
+    }
+
+    fn uses_selection(&self) -> bool {
+        true
+    }
+
+    fn preview(&self) -> &[Pos2] {
+        &self.outline
+    }
+
+    fn properties_ui(&mut self, ui: &mut Ui, _color: Color32) {
+        brush_props::select(ui, &mut self.props);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,28 @@
+// app/brushes/select_props.rs
+
+use serde::{Deserialize, Serialize};
+
+/// How a selection drag encloses elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarqueeShape {
+    #[default]
+    Rectangle,
+    Lasso,
+}
+
+impl MarqueeShape {
+    pub const ALL: [MarqueeShape; 2] = [MarqueeShape::Rectangle, MarqueeShape::Lasso];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MarqueeShape::Rectangle => "Rectangle",
+            MarqueeShape::Lasso => "Lasso",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelectProps {
+    pub shape: MarqueeShape,
+}
@@ -0,0 +1,75 @@
+// app/camera.rs
+//! View transform between world space (where canvas geometry is stored) and
+//! screen space (where egui reports pointer positions).
+
+use eframe::egui::{Pos2, Rect, Vec2};
+use eframe::emath::TSTransform;
+
+/// Smallest zoom factor reachable by wheel or pinch.
+pub const MIN_ZOOM: f32 = 0.05;
+
+/// Largest zoom factor reachable by wheel or pinch.
+pub const MAX_ZOOM: f32 = 40.0;
+
+/// Share of the viewport that fit-to-content fills.
+const FIT_PADDING: f32 = 0.9;
+
+/// Pan/zoom state of the canvas view.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    /// World position shown at the top-left corner of the viewport.
+    pub pan: Vec2,
+    /// Screen points per world unit.
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    /// Transform from world to screen coordinates for `viewport`.
+    pub fn world_to_screen(&self, viewport: Rect) -> TSTransform {
+        TSTransform::new(viewport.min.to_vec2() - self.pan * self.zoom, self.zoom)
+    }
+
+    /// Map a screen position (e.g. the pointer) into world space.
+    pub fn screen_to_world(&self, viewport: Rect, pos: Pos2) -> Pos2 {
+        self.world_to_screen(viewport).inverse().mul_pos(pos)
+    }
+
+    /// World-space area visible in `viewport`.
+    pub fn visible_world(&self, viewport: Rect) -> Rect {
+        self.world_to_screen(viewport).inverse().mul_rect(viewport)
+    }
+
+    /// Move the view by a screen-space drag.
+    pub fn pan_by(&mut self, screen_delta: Vec2) {
+        self.pan -= screen_delta / self.zoom;
+    }
+
+    /// Multiply the zoom by `factor`, keeping the world point under `anchor`
+    /// (a screen position) fixed.
+    pub fn zoom_at(&mut self, viewport: Rect, anchor: Pos2, factor: f32) {
+        let world = self.screen_to_world(viewport, anchor);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = world.to_vec2() - (anchor - viewport.min) / self.zoom;
+    }
+
+    /// Center `bounds` (world space) in `viewport`, zoomed to fit.
+    pub fn fit(&mut self, viewport: Rect, bounds: Rect) {
+        let size = bounds.size().max(Vec2::splat(1.0));
+        let zoom = (viewport.width() / size.x).min(viewport.height() / size.y) * FIT_PADDING;
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = bounds.center().to_vec2() - viewport.size() * 0.5 / self.zoom;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,436 @@
+// app/cli.rs
+//! Command-line mode: load a document (or play a replay), grow it headlessly
+//! and render it, or a time-lapse of it, to files without opening a window.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use crate::app::document::{Document, DocumentError};
+use crate::app::export::timelapse::{Recorder, TimelapseFormat, TimelapseSettings};
+use crate::app::export::{raster, svg, ExportError};
+use crate::app::replay::{Recording, ReplayError, REPLAY_EXTENSION};
+use crate::app::state::AppState;
+
+pub const USAGE: &str = "\
+Usage:
+  crystal_painter                      open the editor
+  crystal_painter render <in.crystal|in.replay> --out <file.png|file.svg> [options]
+  crystal_painter timelapse <in.crystal|in.replay> --out <file.gif|name.png> [options]
+
+A replay is played back as fast as possible; `render` always plays it to the end.
+
+Options:
+  --steps <n>        simulation steps to run before rendering (default 0)
+  --seed <n>         override the seed stored in the document
+  --props <doc>      take brush settings from another .crystal document
+  --size <w>x<h>     output size in pixels (default 2048x2048, at most
+                     8192x8192 worth of pixels)
+  --growth <speed>   growth speed; negative decays (default 0.35)
+  --contain          keep growth inside the document's containment bounds,
+                     or the initial framing without any
+
+Time-lapse options (name.png writes name_0000.png, name_0001.png, ...):
+  --frames <n>       number of frames (default 120)
+  --every <n>        simulation steps between frames (default 4)
+  --fps <n>          GIF playback rate (default 30)";
+
+const DEFAULT_SIZE: (u32, u32) = (2048, 2048);
+
+/// Largest output accepted by `--size`, in pixels (8192×8192).
+const MAX_PIXELS: u64 = 8192 * 8192;
+
+/// Options for `render`.
+#[derive(Clone, Debug)]
+pub struct RenderArgs {
+    pub input: PathBuf,
+    pub out: PathBuf,
+    pub steps: u64,
+    pub seed: Option<u64>,
+    /// Document whose brush settings replace the input's.
+    pub props: Option<PathBuf>,
+    pub size: (u32, u32),
+    pub growth: Option<f32>,
+    pub contain: bool,
+}
+
+/// Options for `timelapse`; `render.steps` are run before the first frame.
+#[derive(Clone, Debug)]
+pub struct TimelapseArgs {
+    pub render: RenderArgs,
+    pub settings: TimelapseSettings,
+}
+
+/// What the process was asked to do.
+#[derive(Clone, Debug)]
+pub enum Command {
+    Gui,
+    Help,
+    Render(RenderArgs),
+    Timelapse(TimelapseArgs),
+}
+
+/// Errors from parsing arguments or running a command.
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Document(DocumentError),
+    Replay(ReplayError),
+    Export(ExportError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(e) => write!(f, "{e}"),
+            CliError::Document(e) => write!(f, "could not load document: {e}"),
+            CliError::Replay(e) => write!(f, "could not load replay: {e}"),
+            CliError::Export(e) => write!(f, "could not write output: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<DocumentError> for CliError {
+    fn from(e: DocumentError) -> Self {
+        CliError::Document(e)
+    }
+}
+
+impl From<ReplayError> for CliError {
+    fn from(e: ReplayError) -> Self {
+        CliError::Replay(e)
+    }
+}
+
+impl From<ExportError> for CliError {
+    fn from(e: ExportError) -> Self {
+        CliError::Export(e)
+    }
+}
+
+/// Handle the command line. Returns `None` when the editor should open,
+/// otherwise the process exit code.
+pub fn run(args: impl Iterator<Item = String>) -> Option<i32> {
+    match parse(args) {
+        Ok(Command::Gui) => None,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            Some(0)
+        }
+        Ok(Command::Render(args)) => Some(report(render(&args))),
+        Ok(Command::Timelapse(args)) => Some(report(timelapse(&args))),
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            Some(2)
+        }
+    }
+}
+
+/// Parse arguments (without the program name).
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, CliError> {
+    let Some(command) = args.next() else {
+        return Ok(Command::Gui);
+    };
+    match command.as_str() {
+        "render" => {
+            let (render, _) = parse_options(args, false)?;
+            Ok(Command::Render(render))
+        }
+        "timelapse" => {
+            let (render, settings) = parse_options(args, true)?;
+            Ok(Command::Timelapse(TimelapseArgs { render, settings }))
+        }
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        other => Err(usage(format!("unknown command \"{other}\""))),
+    }
+}
+
+/// Parse the options shared by `render` and `timelapse`; the time-lapse ones
+/// are only accepted when `timelapse` is set.
+fn parse_options(
+    mut args: impl Iterator<Item = String>,
+    timelapse: bool,
+) -> Result<(RenderArgs, TimelapseSettings), CliError> {
+    let mut settings = TimelapseSettings::default();
+    let mut input = None;
+    let mut out = None;
+    let mut steps = 0;
+    let mut seed = None;
+    let mut props = None;
+    let mut size = DEFAULT_SIZE;
+    let mut growth = None;
+    let mut contain = false;
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| usage(format!("{flag} needs a value")))
+        };
+        match arg.as_str() {
+            "--out" | "-o" => out = Some(PathBuf::from(value("--out")?)),
+            "--steps" => steps = number(&value("--steps")?, "--steps")?,
+            "--seed" => seed = Some(number(&value("--seed")?, "--seed")?),
+            "--props" => props = Some(PathBuf::from(value("--props")?)),
+            "--size" => size = parse_size(&value("--size")?)?,
+            "--growth" => growth = Some(number(&value("--growth")?, "--growth")?),
+            "--contain" => contain = true,
+            "--frames" if timelapse => settings.frames = number(&value("--frames")?, "--frames")?,
+            "--every" if timelapse => settings.every = number(&value("--every")?, "--every")?,
+            "--fps" if timelapse => settings.fps = number(&value("--fps")?, "--fps")?,
+            flag if flag.starts_with('-') => {
+                return Err(usage(format!("unknown option \"{flag}\"")));
+            }
+            path if input.is_none() => input = Some(PathBuf::from(path)),
+            extra => return Err(usage(format!("unexpected argument \"{extra}\""))),
+        }
+    }
+    if settings.frames == 0 {
+        return Err(usage("--frames must be at least 1".to_owned()));
+    }
+    if settings.fps == 0 {
+        return Err(usage("--fps must be at least 1".to_owned()));
+    }
+
+    let render = RenderArgs {
+        input: input.ok_or_else(|| usage("an input document is required".to_owned()))?,
+        out: out.ok_or_else(|| usage("--out <file> is required".to_owned()))?,
+        steps,
+        seed,
+        props,
+        size,
+        growth,
+        contain,
+    };
+    Ok((render, settings))
+}
+
+/// Print the outcome of a command; returns the exit code.
+fn report(result: Result<String, CliError>) -> i32 {
+    match result {
+        Ok(message) => {
+            println!("{message}");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            1
+        }
+    }
+}
+
+/// Load the input into a fresh, unpaused state framed for `args.size`,
+/// run `args.steps` steps, and return it with the containment bounds in use
+/// and the step the input started at.
+///
+/// The state skips the user's config, so batch output doesn't depend on
+/// whoever runs it.
+fn prepare(args: &RenderArgs) -> Result<(AppState, Option<Rect>, u64), CliError> {
+    let mut state = AppState::without_user_config();
+    let is_replay = args
+        .input
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(REPLAY_EXTENSION));
+    if is_replay {
+        let mut recording = Recording::load(&args.input)?;
+        if let Some(seed) = args.seed {
+            recording.start.seed = seed;
+        }
+        state.play(recording, true);
+    } else {
+        let mut document = Document::load(&args.input)?;
+        if let Some(seed) = args.seed {
+            document.seed = seed;
+        }
+        document.apply_to(&mut state);
+    }
+    if let Some(props) = &args.props {
+        Document::load(props)?.apply_brush_props(&mut state);
+    }
+    let start = state.sim.step;
+    if let Some(growth) = args.growth {
+        state.growth_speed = growth;
+    }
+    state.paused = false;
+
+    let (width, height) = args.size;
+    state.canvas_rect = Rect::from_min_size(Pos2::ZERO, Vec2::new(width as f32, height as f32));
+
+    // Containment uses the stored bounds, or the framing of the content
+    // before it grows
+    state.fit_to_content();
+    if args.contain && state.sim.bounds.is_none() {
+        state.contain_to_view();
+    }
+    state.contain_growth = args.contain;
+    let contain = state.contain_bounds();
+    for _ in 0..args.steps {
+        state.tick(contain);
+    }
+    Ok((state, contain, start))
+}
+
+/// Load, simulate and render as described by `args`; returns a summary line.
+pub fn render(args: &RenderArgs) -> Result<String, CliError> {
+    let format = OutputFormat::from_path(&args.out)?;
+    let (mut state, contain, start) = prepare(args)?;
+    while state.player.is_some() {
+        state.tick(contain);
+    }
+    let (width, height) = args.size;
+    let steps = state.sim.step - start;
+
+    let view = contain.unwrap_or_else(|| {
+        state.fit_to_content();
+        state.camera.visible_world(state.canvas_rect)
+    });
+    let scene = state.export_scene();
+
+    match format {
+        OutputFormat::Png => {
+            raster::render_scene(&scene, view, width, height).write_png(&args.out)?;
+            Ok(format!(
+                "Rendered {width}×{height} after {steps} steps to {}",
+                args.out.display()
+            ))
+        }
+        OutputFormat::Svg => {
+            svg::write_svg(&args.out, &svg::render_svg(&scene, view))?;
+            Ok(format!(
+                "Rendered SVG after {steps} steps to {}",
+                args.out.display()
+            ))
+        }
+    }
+}
+
+/// Record a time-lapse as described by `args`; returns a summary line.
+///
+/// The simulation is deterministic, so a first pass runs it to the end to
+/// find a framing that holds the content throughout, then a second pass
+/// records it.
+pub fn timelapse(args: &TimelapseArgs) -> Result<String, CliError> {
+    let render = &args.render;
+    let mut settings = args.settings.clone();
+    settings.format = TimelapseFormat::from_path(&render.out).ok_or_else(|| {
+        usage(format!(
+            "can't tell the time-lapse format of \"{}\"; use .gif or .png",
+            render.out.display()
+        ))
+    })?;
+    let total_steps =
+        u64::from(settings.frames.saturating_sub(1)) * u64::from(settings.every.max(1));
+
+    let (mut state, contain, _) = prepare(render)?;
+    let view = match contain {
+        Some(bounds) => bounds,
+        None => {
+            let (mut probe, ..) = prepare(render)?;
+            let start = probe.content_bounds();
+            for _ in 0..total_steps {
+                probe.tick(None);
+            }
+            let bounds = match (start, probe.content_bounds()) {
+                (Some(a), Some(b)) => Some(a.union(b)),
+                (a, b) => a.or(b),
+            };
+            if let Some(bounds) = bounds {
+                state.camera.fit(state.canvas_rect, bounds);
+            }
+            state.camera.visible_world(state.canvas_rect)
+        }
+    };
+
+    let (width, height) = render.size;
+    let mut recorder = Recorder::start(&render.out, settings, view, width, height)?;
+    while !recorder.on_step(&state.export_scene())? {
+        state.tick(contain);
+    }
+    let frames = recorder.finish()?;
+
+    Ok(format!(
+        "Recorded {frames} frames of {width}×{height} to {}",
+        render.out.display()
+    ))
+}
+
+/// Output file type, chosen by extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Png,
+    Svg,
+}
+
+impl OutputFormat {
+    fn from_path(path: &Path) -> Result<Self, CliError> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("png") => Ok(OutputFormat::Png),
+            Some("svg") => Ok(OutputFormat::Svg),
+            _ => Err(usage(format!(
+                "can't tell the output format of \"{}\"; use .png or .svg",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// Parse `<w>x<h>`, e.g. `2048x2048`.
+fn parse_size(text: &str) -> Result<(u32, u32), CliError> {
+    let (w, h) = text
+        .split_once(['x', 'X'])
+        .ok_or_else(|| usage(format!("size \"{text}\" should look like 2048x2048")))?;
+    let w: u32 = number(w, "--size")?;
+    let h: u32 = number(h, "--size")?;
+    if w == 0 || h == 0 {
+        return Err(usage(format!("size \"{text}\" must be at least 1x1")));
+    }
+    if u64::from(w) * u64::from(h) > MAX_PIXELS {
+        return Err(usage(format!(
+            "size \"{text}\" is too large; at most {MAX_PIXELS} pixels"
+        )));
+    }
+    Ok((w, h))
+}
+
+fn number<T: std::str::FromStr>(text: &str, flag: &str) -> Result<T, CliError> {
+    text.trim()
+        .parse()
+        .map_err(|_| usage(format!("{flag}: \"{text}\" is not a valid number")))
+}
+
+fn usage(message: String) -> CliError {
+    CliError::Usage(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_is_bounded() {
+        assert_eq!(parse_size("640x480").unwrap(), (640, 480));
+        assert_eq!(parse_size("8192X8192").unwrap(), (8192, 8192));
+        assert!(parse_size("0x10").is_err());
+        assert!(parse_size("8193x8192").is_err());
+        assert!(parse_size("65536x65536").is_err());
+        assert!(parse_size("4294967295x4294967295").is_err());
+    }
+
+    #[test]
+    fn frames_and_fps_must_be_positive() {
+        let parse = |extra: &[&str]| {
+            let args = ["in.crystal", "--out", "out.gif"].iter().chain(extra);
+            parse_options(args.map(|a| a.to_string()), true)
+        };
+        let (_, settings) = parse(&["--frames", "3", "--fps", "12"]).unwrap();
+        assert_eq!((settings.frames, settings.fps), (3, 12));
+        assert!(parse(&["--frames", "0"]).is_err());
+        assert!(parse(&["--fps", "0"]).is_err());
+    }
+}
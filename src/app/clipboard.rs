@@ -0,0 +1,253 @@
+// app/clipboard.rs
+//! Canvas elements on the system clipboard.
+//!
+//! Copied strokes, blots and drips travel as a JSON fragment in the
+//! clipboard's text, so they can be pasted into this or another running
+//! document with all their brush data; crystals that were still growing keep
+//! growing and drips keep running. A
+//! rendered PNG can be copied instead for other applications (the clipboard
+//! holds one of the two at a time).
+
+use eframe::egui::{Color32, ColorImage, Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::crystal::StrokeData;
+use crate::app::brushes::drip::Drip;
+use crate::app::export::raster::render_scene;
+use crate::app::export::ExportScene;
+use crate::app::layers::Layer;
+use crate::app::selection::Selection;
+use crate::app::utils::math::Affine;
+
+/// Marks clipboard text as a fragment rather than ordinary text.
+const FRAGMENT_FORMAT: &str = "crystal_painter/fragment";
+
+/// Current fragment format; bump when its layout changes incompatibly.
+pub const FRAGMENT_VERSION: u32 = 2;
+
+/// Blank border around a copied image, in canvas points.
+const IMAGE_MARGIN: f32 = 8.0;
+
+/// Longest side of a copied image, in pixels.
+const MAX_IMAGE_SIDE: f32 = 4096.0;
+
+/// Strokes, blots and drips lifted off a layer or the whole canvas.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Fragment {
+    pub format: String,
+    pub version: u32,
+    /// Canvas clock when copied; segment birth times are rebased on paste.
+    pub time: f64,
+    pub strokes: Vec<StrokeData>,
+    pub blots: Vec<Blot>,
+    /// Version 1 fragments have none.
+    #[serde(default)]
+    pub drips: Vec<Drip>,
+}
+
+impl Fragment {
+    fn new(time: f64) -> Self {
+        Self {
+            format: FRAGMENT_FORMAT.to_owned(),
+            version: FRAGMENT_VERSION,
+            time,
+            strokes: Vec::new(),
+            blots: Vec::new(),
+            drips: Vec::new(),
+        }
+    }
+
+    /// The elements of `selection` on `layer`, or all of them without one.
+    /// Selections hold no drips.
+    pub fn copy(layer: &Layer, selection: Option<&Selection>, time: f64) -> Self {
+        let (strokes, blots, drips) = match selection {
+            Some(selection) => (
+                selection
+                    .strokes
+                    .iter()
+                    .filter_map(|&i| layer.strokes.get(i).cloned())
+                    .collect(),
+                selection
+                    .blots
+                    .iter()
+                    .filter_map(|&i| layer.blots.get(i).cloned())
+                    .collect(),
+                Vec::new(),
+            ),
+            None => (layer.strokes.clone(), layer.blots.clone(), layer.drips.clone()),
+        };
+        Self {
+            strokes,
+            blots,
+            drips,
+            ..Self::new(time)
+        }
+    }
+
+    /// Everything on the visible `layers`, flattened bottom to top. Layer
+    /// opacity and blend modes are not carried over.
+    pub fn copy_canvas(layers: &[Layer], time: f64) -> Self {
+        let mut fragment = Self::new(time);
+        for layer in layers.iter().filter(|l| l.visible) {
+            fragment.strokes.extend(layer.strokes.iter().cloned());
+            fragment.blots.extend(layer.blots.iter().cloned());
+            fragment.drips.extend(layer.drips.iter().cloned());
+        }
+        fragment
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty() && self.blots.is_empty() && self.drips.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Parse clipboard text; `None` if it isn't a fragment this version can read.
+    pub fn from_json(text: &str) -> Option<Self> {
+        let fragment: Fragment = serde_json::from_str(text.trim()).ok()?;
+        (fragment.format == FRAGMENT_FORMAT && fragment.version <= FRAGMENT_VERSION)
+            .then_some(fragment)
+    }
+
+    /// Box around the fragment's segments, blots and drips.
+    pub fn bounds(&self) -> Option<Rect> {
+        let mut bounds = Rect::NOTHING;
+        for seg in self.strokes.iter().flat_map(|s| &s.segments) {
+            bounds.extend_with(seg.start);
+            bounds.extend_with(seg.end);
+        }
+        for drip in &self.drips {
+            let runs = drip.runs.iter().flat_map(|r| &r.points);
+            for &p in drip.path.iter().chain(runs) {
+                bounds.extend_with(p);
+            }
+        }
+        for b in &self.blots {
+            bounds = bounds.union(Rect::from_center_size(b.pos, Vec2::splat(b.radius * 2.0)));
+        }
+        bounds.is_finite().then_some(bounds)
+    }
+
+    /// Move the fragment so it is centered on `center`, and shift segment
+    /// birth times so their ages carry over to a canvas clock at `now`.
+    pub fn place(&mut self, center: Pos2, now: f64) {
+        let Some(bounds) = self.bounds() else {
+            return;
+        };
+        let shift = Affine::translate(center - bounds.center());
+        let age_shift = now - self.time;
+        for stroke in &mut self.strokes {
+            for seg in &mut stroke.segments {
+                seg.start = shift.apply(seg.start);
+                seg.end = shift.apply(seg.end);
+                seg.born += age_shift;
+            }
+        }
+        for b in &mut self.blots {
+            b.pos = shift.apply(b.pos);
+        }
+        for drip in &mut self.drips {
+            let runs = drip.runs.iter_mut().flat_map(|r| &mut r.points);
+            for p in drip.path.iter_mut().chain(runs) {
+                *p = shift.apply(*p);
+            }
+        }
+        self.time = now;
+    }
+
+    /// Render the fragment on a transparent background at `scale` pixels
+    /// per canvas point, capped to a sensible size.
+    pub fn render(&self, scale: f32, base_size: f32) -> Option<ColorImage> {
+        let view = self.bounds()?.expand(IMAGE_MARGIN);
+        let scale = scale.min(MAX_IMAGE_SIDE / view.width().max(view.height()));
+        let width = (view.width() * scale).round().max(1.0) as u32;
+        let height = (view.height() * scale).round().max(1.0) as u32;
+
+        let mut layer = Layer::new(0, "Clipboard");
+        layer.strokes = self.strokes.clone();
+        layer.blots = self.blots.clone();
+        layer.drips = self.drips.clone();
+        let scene = ExportScene {
+            layers: std::slice::from_ref(&layer),
+            background: Color32::TRANSPARENT,
+            base_size,
+        };
+        let image = render_scene(&scene, view, width, height);
+        Some(ColorImage::from_rgba_unmultiplied(
+            [width as usize, height as usize],
+            &image.to_rgba8(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::brushes::drip::DripRun;
+    use crate::app::state::AppState;
+
+    fn drip(x: f32) -> Drip {
+        Drip {
+            path: vec![Pos2::new(x, 0.0), Pos2::new(x + 10.0, 0.0)],
+            color: Color32::WHITE,
+            thickness: 2.0,
+            runs: vec![DripRun {
+                points: vec![Pos2::new(x + 5.0, 0.0), Pos2::new(x + 5.0, 20.0)],
+                velocity: 10.0,
+                paint: 30.0,
+                flowing: true,
+            }],
+            gravity: 1.2,
+            viscosity: 0.9,
+        }
+    }
+
+    #[test]
+    fn canvas_copies_carry_drips_of_visible_layers() {
+        let mut layers = vec![Layer::new(0, "a"), Layer::new(1, "b"), Layer::new(2, "c")];
+        layers[0].drips.push(drip(0.0));
+        layers[1].drips.push(drip(100.0));
+        layers[2].drips.push(drip(200.0));
+        layers[2].visible = false;
+
+        let fragment = Fragment::copy_canvas(&layers, 0.0);
+        let mut fragment = Fragment::from_json(&fragment.to_json().unwrap()).unwrap();
+        assert_eq!(fragment.drips.len(), 2);
+        assert_eq!(fragment.bounds(), Some(Rect::from_min_max(Pos2::ZERO, Pos2::new(110.0, 20.0))));
+
+        fragment.place(Pos2::new(55.0, 110.0), 0.0);
+        assert_eq!(fragment.drips[0].path[0], Pos2::new(0.0, 100.0));
+        assert_eq!(fragment.drips[1].runs[0].points[1], Pos2::new(105.0, 120.0));
+    }
+
+    #[test]
+    fn version_1_fragments_paste_without_drips() {
+        let text = r#"{"format":"crystal_painter/fragment","version":1,"time":0.0,
+            "strokes":[],"blots":[{"pos":{"x":1.0,"y":2.0},"radius":3.0,
+            "color":[255,255,255,255],"softness":0.0,"opacity":1.0,"blend":"Normal"}]}"#;
+        let fragment = Fragment::from_json(text).unwrap();
+        assert_eq!(fragment.blots.len(), 1);
+        assert!(fragment.drips.is_empty());
+    }
+
+    #[test]
+    fn pasted_drips_undo_and_redo() {
+        let mut source = Layer::new(0, "source");
+        source.drips.push(drip(0.0));
+        let text = Fragment::copy(&source, None, 0.0).to_json().unwrap();
+
+        let mut state = AppState::without_user_config();
+        state.layers[0].drips.push(drip(300.0));
+        state.paste(&text, Pos2::new(50.0, 50.0));
+        assert_eq!(state.layers[0].drips.len(), 2);
+        state.undo();
+        assert_eq!(state.layers[0].drips.len(), 1);
+        assert_eq!(state.layers[0].drips[0].path[0], Pos2::new(300.0, 0.0));
+        state.redo();
+        assert_eq!(state.layers[0].drips.len(), 2);
+        assert_eq!(state.layers[0].drips[1].path[0], Pos2::new(45.0, 40.0));
+    }
+}
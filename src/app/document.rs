@@ -0,0 +1,247 @@
+// app/document.rs
+//! Native `.crystal` project file: a versioned JSON snapshot of the canvas.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use eframe::egui::{Color32, Rect};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app::brushes::blotter::Blotter;
+use crate::app::brushes::blotter_props::BlotterProps;
+use crate::app::brushes::crystal::CrystalBrush;
+use crate::app::brushes::crystal_props::CrystalProps;
+use crate::app::brushes::drip::DripBrush;
+use crate::app::brushes::drip_props::DripProps;
+use crate::app::layers::Layer;
+use crate::app::simulation::Simulation;
+use crate::app::state::AppState;
+
+/// Current on-disk format version. Bump together with a migration step.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// File extension used by open/save dialogs.
+pub const FILE_EXTENSION: &str = "crystal";
+
+/// Errors raised while reading or writing a project file.
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Io(e) => write!(f, "I/O error: {e}"),
+            DocumentError::Parse(e) => write!(f, "invalid project file: {e}"),
+            DocumentError::UnsupportedVersion(v) => write!(
+                f,
+                "project file version {v} is newer than supported version {FORMAT_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<std::io::Error> for DocumentError {
+    fn from(e: std::io::Error) -> Self {
+        DocumentError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(e: serde_json::Error) -> Self {
+        DocumentError::Parse(e)
+    }
+}
+
+/// Everything needed to restore a drawing.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub canvas_bg: Color32,
+    pub swatches: Vec<Color32>,
+    /// Bottom to top.
+    #[serde(default)]
+    pub layers: Vec<Layer>,
+    #[serde(default)]
+    pub active_layer: usize,
+    pub crystal_props: CrystalProps,
+    pub drip_props: DripProps,
+    pub blotter_props: BlotterProps,
+
+    /// Simulation seed; with the drawing it determines how growth unfolds.
+    #[serde(default)]
+    pub seed: u64,
+    /// Completed simulation steps; segment `born` stamps are relative to it.
+    #[serde(default)]
+    pub step: u64,
+    /// Brush input events seen so far, so new strokes continue the sequence.
+    #[serde(default)]
+    pub events: u64,
+    /// World rectangle growth is contained in, once containment was used.
+    #[serde(default)]
+    pub bounds: Option<Rect>,
+}
+
+impl Document {
+    /// Snapshot the persistent parts of the app state.
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            canvas_bg: state.canvas_bg,
+            swatches: state.swatches.clone(),
+            layers: state.layers.clone(),
+            active_layer: state.active_layer,
+            crystal_props: state
+                .brushes
+                .typed::<CrystalBrush>()
+                .map(|b| b.props.clone())
+                .unwrap_or_default(),
+            drip_props: state
+                .brushes
+                .typed::<DripBrush>()
+                .map(|b| b.props.clone())
+                .unwrap_or_default(),
+            blotter_props: state
+                .brushes
+                .typed::<Blotter>()
+                .map(|b| b.props.clone())
+                .unwrap_or_default(),
+            seed: state.sim.seed,
+            step: state.sim.step,
+            events: state.sim.events,
+            bounds: state.sim.bounds,
+        }
+    }
+
+    /// Replace the app's canvas and brush settings with this document.
+    pub fn apply_to(self, state: &mut AppState) {
+        self.apply_brush_props(state);
+        state.canvas_bg = self.canvas_bg;
+        state.swatches = self.swatches;
+        state.selected_swatch = None;
+        state.layers = self.layers;
+        state.selection = None;
+        if state.layers.is_empty() {
+            state.layers.push(Layer::new(0, "Layer 1"));
+        }
+        state.active_layer = self.active_layer.min(state.layers.len() - 1);
+        state.next_layer_id = state.layers.iter().map(|l| l.id + 1).max().unwrap_or(0);
+        let mut sim = Simulation::new(self.seed);
+        sim.step = self.step;
+        sim.events = self.events;
+        sim.bounds = self.bounds;
+        state.sim = sim;
+    }
+
+    /// Replace only the app's brush settings with this document's.
+    pub fn apply_brush_props(&self, state: &mut AppState) {
+        if let Some(brush) = state.brushes.typed_mut::<CrystalBrush>() {
+            brush.props = self.crystal_props.clone();
+        }
+        if let Some(brush) = state.brushes.typed_mut::<DripBrush>() {
+            brush.props = self.drip_props.clone();
+        }
+        if let Some(brush) = state.brushes.typed_mut::<Blotter>() {
+            brush.props = self.blotter_props.clone();
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a document of any known version, migrating it to the current one.
+    pub fn from_json(text: &str) -> Result<Self, DocumentError> {
+        Self::from_value(serde_json::from_str(text)?)
+    }
+
+    /// Parse an already-decoded document, migrating it if needed.
+    pub fn from_value(value: Value) -> Result<Self, DocumentError> {
+        Ok(serde_json::from_value(migrate(value)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), DocumentError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, DocumentError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// Upgrade a raw JSON document to `FORMAT_VERSION`. There is only one format
+/// so far; fields added to it since are optional. When the version is bumped,
+/// older documents are upgraded here step by step.
+fn migrate(value: Value) -> Result<Value, DocumentError> {
+    // Without a version serde reports the missing field.
+    let version = value.get("version").and_then(Value::as_u64);
+    if let Some(version) = version.filter(|&v| v > u64::from(FORMAT_VERSION)) {
+        let version = u32::try_from(version).unwrap_or(u32::MAX);
+        return Err(DocumentError::UnsupportedVersion(version));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Pos2;
+
+    use super::*;
+
+    /// A document with only the fields every version 1 file has.
+    const MINIMAL_DOCUMENT: &str = r#"{
+      "version": 1,
+      "canvas_bg": [59, 47, 47, 255],
+      "swatches": [[120, 200, 240, 255]],
+      "crystal_props": {"branch_angle": 0.5},
+      "drip_props": {},
+      "blotter_props": {}
+    }"#;
+
+    #[test]
+    fn optional_fields_default() {
+        let doc = Document::from_json(MINIMAL_DOCUMENT).expect("minimal documents load");
+        assert!(doc.layers.is_empty());
+        assert_eq!((doc.active_layer, doc.seed, doc.step, doc.events), (0, 0, 0, 0));
+        assert_eq!(doc.bounds, None);
+        assert_eq!(doc.crystal_props.branch_angle, 0.5);
+        assert_eq!(doc.crystal_props.min_segment, CrystalProps::default().min_segment);
+
+        let mut state = AppState::without_user_config();
+        doc.apply_to(&mut state);
+        assert_eq!(state.layers.len(), 1);
+    }
+
+    #[test]
+    fn current_documents_round_trip() {
+        let mut state = AppState::without_user_config();
+        state.sim = Simulation::new(99);
+        state.sim.bounds = Some(Rect::from_min_max(Pos2::ZERO, Pos2::new(640.0, 480.0)));
+        state.pointer_down(Pos2::new(10.0, 10.0));
+        state.pointer_drag(Pos2::new(80.0, 40.0));
+        state.pointer_up();
+
+        let text = Document::from_state(&state).to_json().unwrap();
+        let again = Document::from_json(&text).unwrap();
+        assert_eq!(again.seed, 99);
+        assert_eq!(again.layers[0].strokes.len(), 1);
+        assert_eq!(again.to_json().unwrap(), text);
+    }
+
+    #[test]
+    fn newer_documents_are_rejected() {
+        let text = format!(r#"{{"version": {}}}"#, FORMAT_VERSION + 1);
+        assert!(matches!(
+            Document::from_json(&text),
+            Err(DocumentError::UnsupportedVersion(v)) if v == FORMAT_VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,45 @@
+// app/export/mod.rs
+//! Offscreen renderers that turn the canvas into image files.
+
+pub mod raster;
+pub mod svg;
+pub mod timelapse;
+
+use std::fmt;
+
+use eframe::egui::Color32;
+
+use crate::app::layers::Layer;
+
+/// Canvas content handed to the offscreen renderers, in paint order.
+pub struct ExportScene<'a> {
+    /// Layers bottom to top; hidden layers are skipped by the renderers.
+    pub layers: &'a [Layer],
+    pub background: Color32,
+    /// Stroke width used when a stroke has no explicit thickness.
+    pub base_size: f32,
+}
+
+/// Errors raised while writing an exported image.
+#[derive(Debug)]
+pub enum ExportError {
+    Io(std::io::Error),
+    Encode(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "I/O error: {e}"),
+            ExportError::Encode(e) => write!(f, "encoding error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
@@ -0,0 +1,500 @@
+// app/export/raster.rs
+//! CPU rasterizer for the canvas. Works without a window or GPU, so it can be
+//! used from tests and batch tools as well as the Export menu.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use eframe::egui::{Color32, Pos2, Rect, Vec2};
+
+use crate::app::blend::BlendMode;
+use crate::app::export::{ExportError, ExportScene};
+use crate::app::layers::Layer;
+use crate::app::painter::CanvasPainter;
+use crate::app::ribbon::{cross, ribbons, Ribbon};
+use crate::app::utils::math::distance_to_segment;
+
+/// Premultiplied RGBA image, blended the same way egui blends shapes.
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    /// Blend mode applied by the drawing calls.
+    pub mode: BlendMode,
+    /// Premultiplied RGBA in 0.0–1.0, row-major.
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Raster {
+    /// Create an image filled with a solid color.
+    ///
+    /// Panics if `width * height` overflows `usize`; callers taking sizes
+    /// from the user should bound them first.
+    pub fn new(width: u32, height: u32, fill: Color32) -> Self {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .expect("raster size overflows usize");
+        Self {
+            width,
+            height,
+            mode: BlendMode::Normal,
+            pixels: vec![premultiplied(fill); len],
+        }
+    }
+
+    /// Index of pixel (`x`, `y`) in `pixels`.
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Blend `color` into one pixel with the given coverage (0.0–1.0).
+    fn blend(&mut self, x: i32, y: i32, color: [f32; 4], coverage: f32) {
+        if coverage <= 0.0 || x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+
+        let i = self.index(x as u32, y as u32);
+        let px = &mut self.pixels[i];
+        *px = self.mode.composite(*px, color.map(|c| c * coverage));
+    }
+
+    /// Iterate the pixels inside `bounds`, blending `color` with the coverage
+    /// returned by `coverage_at` for each pixel center.
+    fn shade(&mut self, bounds: Rect, color: Color32, coverage_at: impl Fn(Pos2) -> f32) {
+        let color = premultiplied(color);
+        if color[3] <= 0.0 {
+            return;
+        }
+
+        let x0 = bounds.min.x.floor().max(0.0) as i32;
+        let y0 = bounds.min.y.floor().max(0.0) as i32;
+        let x1 = bounds.max.x.ceil().min(self.width as f32) as i32;
+        let y1 = bounds.max.y.ceil().min(self.height as f32) as i32;
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                self.blend(x, y, color, coverage_at(p).clamp(0.0, 1.0));
+            }
+        }
+    }
+
+    /// Anti-aliased filled circle.
+    pub fn fill_circle(&mut self, center: Pos2, radius: f32, color: Color32) {
+        let bounds = Rect::from_center_size(center, Vec2::splat(radius * 2.0 + 2.0));
+        self.shade(bounds, color, |p| radius + 0.5 - p.distance(center));
+    }
+
+    /// Anti-aliased ring centered on `radius` with the given stroke width.
+    pub fn stroke_circle(&mut self, center: Pos2, radius: f32, width: f32, color: Color32) {
+        // Hairlines fade out instead of shrinking below a pixel, like egui.
+        let (width, color) = thin_line(width, color);
+        let bounds = Rect::from_center_size(center, Vec2::splat((radius + width) * 2.0 + 2.0));
+        self.shade(bounds, color, |p| {
+            width * 0.5 + 0.5 - (p.distance(center) - radius).abs()
+        });
+    }
+
+    /// Anti-aliased line segment with the given width.
+    pub fn line(&mut self, a: Pos2, b: Pos2, width: f32, color: Color32) {
+        let (width, color) = thin_line(width, color);
+        let bounds = Rect::from_two_pos(a, b).expand(width * 0.5 + 1.0);
+        self.shade(bounds, color, |p| {
+            width * 0.5 + 0.5 - distance_to_segment(p, a, b)
+        });
+    }
+
+    /// Anti-aliased ribbon (in image coordinates), filled with the same
+    /// triangles as the live canvas and feathered over one pixel. The
+    /// triangles don't overlap, so joints aren't painted twice.
+    pub fn ribbon(&mut self, ribbon: &Ribbon, color: Color32) {
+        let color = premultiplied(color);
+        if color[3] <= 0.0 {
+            return;
+        }
+
+        let mesh = ribbon.triangulate(1.0);
+        for tri in mesh.indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|k| {
+                let v = &mesh.vertices[tri[k] as usize];
+                (v.pos, v.opacity * v.strength)
+            });
+            self.fill_triangle(corners, color);
+        }
+    }
+
+    /// Fill a triangle with `color` scaled by the coverage interpolated
+    /// between its corners. Pixel centers on an edge go to one side only, so
+    /// triangles sharing that edge don't both paint them.
+    fn fill_triangle(&mut self, corners: [(Pos2, f32); 3], color: [f32; 4]) {
+        let [(a, ca), (mut b, mut cb), (mut c, mut cc)] = corners;
+        let mut area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+        // Wind the triangle so its inside is left of every edge
+        if area < 0.0 {
+            std::mem::swap(&mut b, &mut c);
+            std::mem::swap(&mut cb, &mut cc);
+            area = -area;
+        }
+
+        let bounds = Rect::from_points(&[a, b, c]);
+        let x0 = bounds.min.x.floor().max(0.0) as i32;
+        let y0 = bounds.min.y.floor().max(0.0) as i32;
+        let x1 = bounds.max.x.ceil().min(self.width as f32) as i32;
+        let y1 = bounds.max.y.ceil().min(self.height as f32) as i32;
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let (wa, wb, wc) = (edge(b, c, p), edge(c, a, p), edge(a, b, p));
+                if covers(wa, b, c) && covers(wb, c, a) && covers(wc, a, b) {
+                    let coverage = (wa * ca + wb * cb + wc * cc) / area;
+                    self.blend(x, y, color, coverage.clamp(0.0, 1.0));
+                }
+            }
+        }
+    }
+
+    /// Lay `layer` (same size) over this image, scaled by `opacity` and
+    /// combined with `mode`.
+    pub fn composite(&mut self, layer: &Raster, opacity: f32, mode: BlendMode) {
+        if opacity <= 0.0 {
+            return;
+        }
+        for (dst, src) in self.pixels.iter_mut().zip(&layer.pixels) {
+            *dst = mode.composite(*dst, src.map(|c| c * opacity));
+        }
+    }
+
+    /// Straight (unpremultiplied) RGBA8 bytes, as expected by image encoders.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.pixels.len() * 4);
+        for px in &self.pixels {
+            let a = px[3];
+            for c in &px[..3] {
+                let v = if a > 0.0 { c / a } else { 0.0 };
+                out.push((v.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+            out.push((a.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+        out
+    }
+
+    /// Encode the image as an 8-bit RGBA PNG.
+    pub fn write_png(&self, path: &Path) -> Result<(), ExportError> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| ExportError::Encode(e.to_string()))?;
+        writer
+            .write_image_data(&self.to_rgba8())
+            .map_err(|e| ExportError::Encode(e.to_string()))?;
+        writer
+            .finish()
+            .map_err(|e| ExportError::Encode(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Maps canvas coordinates onto an output image of arbitrary size.
+///
+/// The canvas `view` is scaled uniformly to fit and centered.
+#[derive(Clone, Copy)]
+pub struct RasterView {
+    pub view: Rect,
+    pub scale: f32,
+    pub offset: Vec2,
+}
+
+impl RasterView {
+    pub fn fit(view: Rect, width: u32, height: u32) -> Self {
+        let sx = width as f32 / view.width().max(1.0);
+        let sy = height as f32 / view.height().max(1.0);
+        let scale = sx.min(sy);
+        let offset = Vec2::new(
+            (width as f32 - view.width() * scale) * 0.5,
+            (height as f32 - view.height() * scale) * 0.5,
+        );
+        Self { view, scale, offset }
+    }
+
+    pub fn to_image(self, p: Pos2) -> Pos2 {
+        ((p - self.view.min) * self.scale + self.offset).to_pos2()
+    }
+}
+
+/// Render the scene in the same order as the live canvas into a new image.
+///
+/// Normal layers at full opacity are drawn straight onto the image, so blot
+/// blend modes mix with everything beneath, as in the live view. Other layers
+/// are drawn into their own buffer and composited with their opacity and mode.
+pub fn render_scene(scene: &ExportScene, view: Rect, width: u32, height: u32) -> Raster {
+    let mut raster = Raster::new(width, height, scene.background);
+    let map = RasterView::fit(view, width, height);
+
+    for layer in scene.layers.iter().filter(|l| l.visible) {
+        let opacity = layer.opacity.clamp(0.0, 1.0);
+        if layer.blend == BlendMode::Normal && opacity >= 1.0 {
+            render_layer(&mut raster, layer, map, scene.base_size);
+            continue;
+        }
+
+        let mut buffer = Raster::new(width, height, Color32::TRANSPARENT);
+        render_layer(&mut buffer, layer, map, scene.base_size);
+        raster.composite(&buffer, opacity, layer.blend);
+    }
+
+    raster
+}
+
+/// Draw one layer's strokes, drips and blots into `raster`.
+fn render_layer(raster: &mut Raster, layer: &Layer, map: RasterView, base_size: f32) {
+    for stroke in &layer.strokes {
+        for ribbon in ribbons(stroke, base_size) {
+            raster.ribbon(&ribbon.mapped(|p| map.to_image(p), map.scale), stroke.color);
+        }
+    }
+
+    for drip in &layer.drips {
+        let w = drip.thickness * map.scale;
+        for p in drip.path.windows(2) {
+            raster.line(map.to_image(p[0]), map.to_image(p[1]), w, drip.color);
+        }
+        for run in &drip.runs {
+            for (a, b, width) in run.pieces(drip.thickness) {
+                raster.line(map.to_image(a), map.to_image(b), width * map.scale, drip.color);
+            }
+            let bead = run.head_radius(drip.thickness);
+            if bead > 0.0 {
+                raster.fill_circle(map.to_image(run.head()), bead * map.scale, drip.color);
+            }
+        }
+    }
+
+    for b in &layer.blots {
+        let center = map.to_image(b.pos);
+        raster.mode = b.blend;
+        raster.fill_circle(center, b.radius * map.scale, CanvasPainter::blot_fill(b));
+
+        if let Some(halo) = CanvasPainter::blot_halo(b) {
+            raster.stroke_circle(center, halo.radius * map.scale, halo.width * map.scale, halo.color);
+        }
+    }
+    raster.mode = BlendMode::Normal;
+}
+
+fn premultiplied(c: Color32) -> [f32; 4] {
+    [
+        c.r() as f32 / 255.0,
+        c.g() as f32 / 255.0,
+        c.b() as f32 / 255.0,
+        c.a() as f32 / 255.0,
+    ]
+}
+
+/// Twice the signed area of triangle `a`, `b`, `p`; positive when `p` is
+/// left of `a` → `b`. Computed from the same end whichever way round the
+/// edge is given, so triangles sharing it get exactly opposite values.
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    if (a.x, a.y) <= (b.x, b.y) {
+        cross(b - a, p - a)
+    } else {
+        -cross(a - b, p - b)
+    }
+}
+
+/// Whether a pixel center with edge value `e` for `a` → `b` is inside:
+/// left of the edge, or on it when the edge is a top or left one.
+fn covers(e: f32, a: Pos2, b: Pos2) -> bool {
+    let d = b - a;
+    e > 0.0 || (e == 0.0 && (d.y > 0.0 || (d.y == 0.0 && d.x < 0.0)))
+}
+
+/// Lines thinner than a pixel are drawn one pixel wide with reduced alpha.
+fn thin_line(width: f32, color: Color32) -> (f32, Color32) {
+    if width < 1.0 {
+        (1.0, color.gamma_multiply(width.max(0.0)))
+    } else {
+        (width, color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::brushes::blotter::Blot;
+
+    const EPS: f32 = 1e-3;
+
+    fn pixel(raster: &Raster, x: u32, y: u32) -> [f32; 4] {
+        raster.pixels[raster.index(x, y)]
+    }
+
+    fn blot(pos: Pos2, radius: f32, color: Color32, softness: f32, opacity: f32) -> Blot {
+        Blot {
+            pos,
+            radius,
+            color,
+            softness,
+            opacity,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    /// Render `layers` over `background`, one canvas point per pixel.
+    fn render(layers: &[Layer], background: Color32) -> Raster {
+        let scene = ExportScene {
+            layers,
+            background,
+            base_size: 2.0,
+        };
+        let view = Rect::from_min_size(Pos2::ZERO, Vec2::splat(100.0));
+        render_scene(&scene, view, 100, 100)
+    }
+
+    #[test]
+    fn blot_fill_carries_its_opacity() {
+        let mut layer = Layer::new(0, "Layer 1");
+        layer.blots.push(blot(Pos2::new(50.5, 50.5), 10.0, Color32::WHITE, 0.0, 0.5));
+        let image = render(&[layer], Color32::TRANSPARENT);
+
+        let alpha = (0.5f32 * 255.0) as u8 as f32 / 255.0;
+        let center = pixel(&image, 50, 50);
+        assert!((center[3] - alpha).abs() < EPS, "{center:?}");
+        assert!((center[0] - alpha).abs() < EPS, "premultiplied: {center:?}");
+        // Anti-aliased edge, nothing beyond it
+        let edge = pixel(&image, 60, 50)[3];
+        assert!(edge > 0.0 && edge < alpha, "{edge}");
+        assert_eq!(pixel(&image, 62, 50)[3], 0.0);
+        assert_eq!(pixel(&image, 0, 0), [0.0; 4]);
+    }
+
+    #[test]
+    fn soft_blot_gets_a_fainter_halo() {
+        let mut layer = Layer::new(0, "Layer 1");
+        let b = blot(Pos2::new(50.5, 50.5), 10.0, Color32::WHITE, 1.0, 1.0);
+        let halo = CanvasPainter::blot_halo(&b).expect("soft blots have a halo");
+        layer.blots.push(b);
+        let image = render(&[layer], Color32::TRANSPARENT);
+
+        // On the ring, outside the fill
+        let x = (50.5 + halo.radius).floor() as u32;
+        let ring = pixel(&image, x, 50)[3];
+        assert!((ring - halo.color.a() as f32 / 255.0).abs() < EPS, "{ring}");
+        assert!((pixel(&image, 50, 50)[3] - 1.0).abs() < EPS);
+    }
+
+    #[test]
+    fn ribbon_coverage_falls_off_at_its_edges() {
+        let mut raster = Raster::new(100, 100, Color32::TRANSPARENT);
+        let mut ribbon = Ribbon::default();
+        ribbon.push(Pos2::new(10.0, 50.5), 4.0, 1.0);
+        ribbon.push(Pos2::new(90.0, 50.5), 4.0, 1.0);
+        raster.ribbon(&ribbon, Color32::WHITE);
+
+        assert!((pixel(&raster, 50, 50)[3] - 1.0).abs() < EPS);
+        assert!((pixel(&raster, 50, 51)[3] - 1.0).abs() < EPS);
+        // Pixel center 2 points off the centerline: half covered
+        assert!((pixel(&raster, 50, 52)[3] - 0.5).abs() < EPS);
+        assert_eq!(pixel(&raster, 50, 53)[3], 0.0);
+    }
+
+    #[test]
+    fn ribbon_joints_are_not_painted_twice() {
+        let mut raster = Raster::new(100, 100, Color32::TRANSPARENT);
+        let mut ribbon = Ribbon::default();
+        ribbon.push(Pos2::new(10.5, 50.5), 6.0, 1.0);
+        ribbon.push(Pos2::new(50.5, 50.5), 6.0, 1.0);
+        ribbon.push(Pos2::new(50.5, 90.5), 6.0, 1.0);
+        let color = Color32::from_white_alpha(128);
+        raster.ribbon(&ribbon, color);
+
+        let alpha = 128.0 / 255.0;
+        for (x, y) in [(30, 50), (50, 50), (51, 51), (50, 70)] {
+            let a = pixel(&raster, x, y)[3];
+            assert!((a - alpha).abs() < EPS, "({x}, {y}): {a}");
+        }
+    }
+
+    #[test]
+    fn sharp_turns_are_painted_once() {
+        let color = Color32::from_white_alpha(128);
+        let alpha = 128.0 / 255.0;
+        // Mitered, then rounded on the left and on the right of the turn
+        for end in [Pos2::new(80.5, 80.5), Pos2::new(20.5, 80.5), Pos2::new(20.5, 20.5)] {
+            let mut raster = Raster::new(100, 100, Color32::TRANSPARENT);
+            let mut ribbon = Ribbon::default();
+            ribbon.push(Pos2::new(10.5, 50.5), 12.0, 1.0);
+            ribbon.push(Pos2::new(50.5, 50.5), 12.0, 1.0);
+            ribbon.push(end, 12.0, 1.0);
+            raster.ribbon(&ribbon, color);
+
+            let most = raster.pixels.iter().map(|px| px[3]).fold(0.0, f32::max);
+            assert!(most < alpha + EPS, "{end:?}: {most}");
+            // No gaps either: pixels well inside the ribbon are fully painted
+            let points = &ribbon.points;
+            for (x, y) in (0..100).flat_map(|y| (0..100).map(move |x| (x, y))) {
+                let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let d = distance_to_segment(p, points[0], points[1])
+                    .min(distance_to_segment(p, points[1], points[2]));
+                if d < 5.0 {
+                    let a = pixel(&raster, x, y)[3];
+                    assert!((a - alpha).abs() < EPS, "{end:?} ({x}, {y}): {a}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn layer_opacity_scales_the_layer() {
+        let mut layer = Layer::new(0, "Layer 1");
+        layer.opacity = 0.5;
+        layer.blots.push(blot(Pos2::new(50.0, 50.0), 20.0, Color32::BLACK, 0.0, 1.0));
+        let image = render(&[layer], Color32::WHITE);
+
+        let center = pixel(&image, 50, 50);
+        for c in &center[..3] {
+            assert!((c - 0.5).abs() < EPS, "{center:?}");
+        }
+        assert!((center[3] - 1.0).abs() < EPS);
+        assert_eq!(pixel(&image, 0, 0), [1.0; 4]);
+    }
+
+    #[test]
+    fn layer_blend_mode_mixes_with_the_layers_below() {
+        let gray = Color32::from_gray(128);
+        let red = Color32::from_rgb(255, 0, 0);
+        let g = 128.0 / 255.0;
+
+        let mut lower = Layer::new(0, "Lower");
+        lower.blots.push(blot(Pos2::new(50.0, 50.0), 40.0, gray, 0.0, 1.0));
+        let mut upper = Layer::new(1, "Upper");
+        upper.blend = BlendMode::Multiply;
+        upper.blots.push(blot(Pos2::new(50.0, 50.0), 20.0, red, 0.0, 1.0));
+        let multiplied = pixel(&render(&[lower.clone(), upper.clone()], Color32::WHITE), 50, 50);
+        for (c, expected) in multiplied.iter().zip([g, 0.0, 0.0, 1.0]) {
+            assert!((c - expected).abs() < EPS, "multiply: {multiplied:?}");
+        }
+
+        upper.blend = BlendMode::Screen;
+        let screened = pixel(&render(&[lower, upper], Color32::WHITE), 50, 50);
+        for (c, expected) in screened.iter().zip([1.0, g, g, 1.0]) {
+            assert!((c - expected).abs() < EPS, "screen: {screened:?}");
+        }
+    }
+
+    #[test]
+    fn hidden_layers_are_skipped() {
+        let mut layer = Layer::new(0, "Layer 1");
+        layer.visible = false;
+        layer.blots.push(blot(Pos2::new(50.0, 50.0), 20.0, Color32::BLACK, 0.0, 1.0));
+        let image = render(&[layer], Color32::WHITE);
+        assert_eq!(pixel(&image, 50, 50), [1.0; 4]);
+    }
+}
@@ -0,0 +1,362 @@
+// app/export/svg.rs
+//! SVG writer: crystal strokes become filled outlines and blots become
+//! circles feathered with radial gradients, so artwork stays editable at any
+//! scale.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use eframe::egui::{Color32, Pos2, Rect};
+
+use crate::app::blend::BlendMode;
+use crate::app::export::{ExportError, ExportScene};
+use crate::app::painter::CanvasPainter;
+use crate::app::ribbon::{join, normals, ribbons, Join};
+
+/// Build an SVG document for the canvas area `view` (in canvas coordinates).
+pub fn render_svg(scene: &ExportScene, view: Rect) -> String {
+    let mut body = String::new();
+    let mut defs = String::new();
+    let mut gradients: HashMap<GradientKey, String> = HashMap::new();
+
+    // Background
+    let _ = writeln!(
+        body,
+        r#"  <rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+        num(view.min.x),
+        num(view.min.y),
+        num(view.width()),
+        num(view.height()),
+        paint_attrs("fill", scene.background),
+    );
+
+    // One group per visible layer, faded by its opacity and blended by its mode
+    for layer in scene.layers.iter().filter(|l| l.visible) {
+        let opacity = layer.opacity.clamp(0.0, 1.0);
+        body.push_str("  <g");
+        if opacity < 1.0 {
+            let _ = write!(body, r#" opacity="{}""#, num(opacity));
+        }
+        body.push_str(&blend_attr(layer.blend));
+        body.push_str(">\n");
+
+        // Crystal strokes, one group per stroke and one outline per ribbon;
+        // ribbons are split where decay faded their segments unevenly
+        for stroke in &layer.strokes {
+            if stroke.segments.is_empty() {
+                continue;
+            }
+
+            let fill = paint_attrs("fill", stroke.color);
+            let _ = writeln!(body, r#"    <g {fill} stroke="none">"#);
+            for ribbon in ribbons(stroke, scene.base_size) {
+                let opacities = &ribbon.opacities;
+                let mut from = 0;
+                while from + 1 < ribbon.points.len() {
+                    let mut to = from + 1;
+                    while to + 1 < ribbon.points.len()
+                        && opacities[to] == opacities[from]
+                        && opacities[to + 1] == opacities[from]
+                    {
+                        to += 1;
+                    }
+                    let opacity = (opacities[from] + opacities[to]) * 0.5;
+                    let _ = write!(
+                        body,
+                        r#"      <path d="{}""#,
+                        outline(&ribbon.points[from..=to], &ribbon.widths[from..=to]),
+                    );
+                    // Faded by decay; replaces the group's opacity, so fold that in
+                    if opacity < 1.0 {
+                        let alpha = stroke.color.to_srgba_unmultiplied()[3] as f32 / 255.0;
+                        let _ = write!(body, r#" fill-opacity="{}""#, num(alpha * opacity.max(0.0)));
+                    }
+                    body.push_str("/>\n");
+                    from = to;
+                }
+            }
+            body.push_str("    </g>\n");
+        }
+
+        // Drips: the drawn path, then each run as tapered lines ending in a bead
+        for drip in &layer.drips {
+            let _ = writeln!(
+                body,
+                r#"    <g {} stroke-linecap="round">"#,
+                paint_attrs("stroke", drip.color),
+            );
+            if drip.path.len() >= 2 {
+                let points: Vec<String> = drip
+                    .path
+                    .iter()
+                    .map(|p| format!("{},{}", num(p.x), num(p.y)))
+                    .collect();
+                let _ = writeln!(
+                    body,
+                    r#"      <polyline points="{}" fill="none" stroke-width="{}" stroke-linejoin="round"/>"#,
+                    points.join(" "),
+                    num(drip.thickness),
+                );
+            }
+            for run in &drip.runs {
+                for (a, b, width) in run.pieces(drip.thickness) {
+                    let _ = writeln!(
+                        body,
+                        r#"      <line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}"/>"#,
+                        num(a.x),
+                        num(a.y),
+                        num(b.x),
+                        num(b.y),
+                        num(width),
+                    );
+                }
+                let bead = run.head_radius(drip.thickness);
+                if bead > 0.0 {
+                    let head = run.head();
+                    let _ = writeln!(
+                        body,
+                        r#"      <circle cx="{}" cy="{}" r="{}" {} stroke="none"/>"#,
+                        num(head.x),
+                        num(head.y),
+                        num(bead),
+                        paint_attrs("fill", drip.color),
+                    );
+                }
+            }
+            body.push_str("    </g>\n");
+        }
+
+        // Blots, feathered where they have softness
+        for b in &layer.blots {
+            let fill = CanvasPainter::blot_fill(b);
+
+            let blend = blend_attr(b.blend);
+
+            let Some(halo) = CanvasPainter::blot_halo(b) else {
+                let _ = writeln!(
+                    body,
+                    r#"    <circle cx="{}" cy="{}" r="{}" {}{blend}/>"#,
+                    num(b.pos.x),
+                    num(b.pos.y),
+                    num(b.radius),
+                    paint_attrs("fill", fill),
+                );
+                continue;
+            };
+
+            let outer = (halo.radius + halo.width * 0.5).max(b.radius);
+            let key = GradientKey {
+                fill,
+                halo: halo.color,
+                inner: (b.radius / outer * 1000.0).round() as u32,
+                ring: (halo.radius / outer * 1000.0).round() as u32,
+            };
+
+            let next_id = gradients.len();
+            let id = gradients.entry(key).or_insert_with(|| {
+                let id = format!("blot{next_id}");
+                write_gradient(&mut defs, &id, &key);
+                id
+            });
+
+            let _ = writeln!(
+                body,
+                r#"    <circle cx="{}" cy="{}" r="{}" fill="url(#{id})"{blend}/>"#,
+                num(b.pos.x),
+                num(b.pos.y),
+                num(outer),
+            );
+        }
+        body.push_str("  </g>\n");
+    }
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        num(view.width()),
+        num(view.height()),
+        num(view.min.x),
+        num(view.min.y),
+        num(view.width()),
+        num(view.height()),
+    );
+    if !defs.is_empty() {
+        let _ = write!(svg, "  <defs>\n{defs}  </defs>\n");
+    }
+    svg.push_str(&body);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Write the SVG produced by [`render_svg`] to disk.
+pub fn write_svg(path: &Path, svg: &str) -> Result<(), ExportError> {
+    fs::write(path, svg)?;
+    Ok(())
+}
+
+/// Blots with identical color and proportions share one gradient definition.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct GradientKey {
+    fill: Color32,
+    halo: Color32,
+    /// Fill edge as a fraction of the outer radius, in thousandths.
+    inner: u32,
+    /// Halo ring center as a fraction of the outer radius, in thousandths.
+    ring: u32,
+}
+
+fn write_gradient(defs: &mut String, id: &str, key: &GradientKey) {
+    let inner = key.inner as f32 / 1000.0;
+    let ring = (key.ring as f32 / 1000.0).max(inner);
+
+    let _ = writeln!(defs, r#"    <radialGradient id="{id}">"#);
+    let [hr, hg, hb, ha] = key.halo.to_srgba_unmultiplied();
+    let [fr, fg, fb, fa] = key.fill.to_srgba_unmultiplied();
+
+    // The last stop keeps the halo hue so the fade doesn't darken toward black.
+    for (offset, [r, g, b], alpha) in [
+        (0.0, [fr, fg, fb], fa),
+        (inner, [fr, fg, fb], fa),
+        (ring, [hr, hg, hb], ha),
+        (1.0, [hr, hg, hb], 0),
+    ] {
+        let _ = writeln!(
+            defs,
+            r##"      <stop offset="{}" stop-color="#{r:02x}{g:02x}{b:02x}" stop-opacity="{}"/>"##,
+            num(offset),
+            num(alpha as f32 / 255.0),
+        );
+    }
+    defs.push_str("    </radialGradient>\n");
+}
+
+/// ` style="mix-blend-mode:…"` for non-normal modes, with a leading space.
+/// CSS has no pigment mode; multiply is the closest match.
+fn blend_attr(mode: BlendMode) -> String {
+    let css = match mode {
+        BlendMode::Normal => return String::new(),
+        BlendMode::Multiply | BlendMode::Pigment => "multiply",
+        BlendMode::Screen => "screen",
+        BlendMode::Additive => "plus-lighter",
+    };
+    format!(r#" style="mix-blend-mode:{css}""#)
+}
+
+/// `fill="#rrggbb" fill-opacity="a"` style attribute pair.
+fn paint_attrs(attr: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == 255 {
+        format!(r##"{attr}="#{r:02x}{g:02x}{b:02x}""##)
+    } else {
+        format!(
+            r##"{attr}="#{r:02x}{g:02x}{b:02x}" {attr}-opacity="{}""##,
+            num(a as f32 / 255.0)
+        )
+    }
+}
+
+/// Path data for the filled outline of a polyline with the given widths:
+/// round caps and the same joins as the live ribbons, see [`join`].
+fn outline(points: &[Pos2], widths: &[f32]) -> String {
+    let radii: Vec<f32> = widths.iter().map(|w| w.max(0.0) * 0.5).collect();
+    let normals = normals(points);
+    let last = points.len() - 1;
+
+    let mut d = String::new();
+    let start = points[0] + normals[0] * radii[0];
+    let _ = write!(d, "M{} {}", num(start.x), num(start.y));
+    left_side(points, &radii, &mut d);
+
+    let end = points[last] - normals[last - 1] * radii[last];
+    let _ = write!(d, " A{r} {r} 0 0 0 {} {}", num(end.x), num(end.y), r = num(radii[last]));
+    let back: Vec<Pos2> = points.iter().rev().copied().collect();
+    let back_radii: Vec<f32> = radii.iter().rev().copied().collect();
+    left_side(&back, &back_radii, &mut d);
+
+    let _ = write!(d, " A{r} {r} 0 0 0 {} {} Z", num(start.x), num(start.y), r = num(radii[0]));
+    d
+}
+
+/// Append the left-hand edge of a polyline, after its first point, to the
+/// path data `d`.
+fn left_side(points: &[Pos2], radii: &[f32], d: &mut String) {
+    let normals = normals(points);
+    let line_to = |d: &mut String, p: Pos2| {
+        let _ = write!(d, " L{} {}", num(p.x), num(p.y));
+    };
+
+    for i in 1..points.len() - 1 {
+        let (p, r) = (points[i], radii[i]);
+        let reach = points[i - 1].distance(p).min(p.distance(points[i + 1])) / r;
+        match join(normals[i - 1], normals[i], reach) {
+            Join::Miter(m) => line_to(d, p + m * r),
+            // Outside of the turn: round it off
+            Join::Round { turn, .. } if turn < 0.0 => {
+                line_to(d, p + normals[i - 1] * r);
+                let to = p + normals[i] * r;
+                let _ = write!(d, " A{r} {r} 0 0 0 {} {}", num(to.x), num(to.y), r = num(r));
+            }
+            // Inside: where the edges cross
+            Join::Round { inner, .. } => line_to(d, p + inner * r),
+        }
+    }
+
+    let end = points.len() - 1;
+    line_to(d, points[end] + normals[end - 1] * radii[end]);
+}
+
+/// Compact number formatting (at most three decimals, no trailing zeros).
+fn num(v: f32) -> String {
+    let s = format!("{v:.3}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_owned() } else { s.to_owned() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Vec2;
+
+    use crate::app::brushes::blotter::Blot;
+    use crate::app::brushes::crystal::StrokeData;
+    use crate::app::layers::Layer;
+
+    #[test]
+    fn one_stroke_and_one_blot_round_trip() {
+        let mut layer = Layer::new(0, "Layer 1");
+        let mut stroke = StrokeData::new(Color32::WHITE, false);
+        stroke.add_segment(Pos2::new(10.0, 10.0), Pos2::new(50.0, 10.0), Vec2::X, [1.0; 2], 0.0);
+        layer.strokes.push(stroke);
+        layer.blots.push(Blot {
+            pos: Pos2::new(30.0, 40.0),
+            radius: 5.0,
+            color: Color32::RED,
+            softness: 0.0,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        });
+        let scene = ExportScene {
+            layers: std::slice::from_ref(&layer),
+            background: Color32::BLACK,
+            base_size: 2.0,
+        };
+        let view = Rect::from_min_size(Pos2::new(-5.0, 0.0), Vec2::new(80.0, 60.0));
+
+        let path = std::env::temp_dir().join(format!("svg-round-trip-{}.svg", std::process::id()));
+        write_svg(&path, &render_svg(&scene, view)).unwrap();
+        let svg = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(svg.contains(r#"width="80" height="60" viewBox="-5 0 80 60""#), "{svg}");
+        let count = |tag: &str| svg.matches(&format!("<{tag} ")).count();
+        assert_eq!((count("rect"), count("path"), count("circle")), (1, 1, 1), "{svg}");
+        // The 2-point wide outline starts on the stroke's left edge
+        assert!(svg.contains(r#"<path d="M10 11 L50 11"#), "{svg}");
+        assert!(svg.contains(r##"<circle cx="30" cy="40" r="5" fill="#ff0000"/>"##), "{svg}");
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+}
@@ -0,0 +1,283 @@
+// app/export/timelapse.rs
+//! Time-lapse recording: samples the scene every few simulation steps and
+//! writes the frames as numbered PNGs or an animated GIF.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::Rect;
+
+use crate::app::export::raster::render_scene;
+use crate::app::export::{ExportError, ExportScene};
+
+/// GIF frame quantization speed: 1 is best quality, 30 fastest.
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+/// How recorded frames are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimelapseFormat {
+    /// `name_0000.png`, `name_0001.png`, … next to the chosen path.
+    #[default]
+    PngSequence,
+    Gif,
+}
+
+impl TimelapseFormat {
+    pub const ALL: [TimelapseFormat; 2] = [TimelapseFormat::PngSequence, TimelapseFormat::Gif];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimelapseFormat::PngSequence => "PNG sequence",
+            TimelapseFormat::Gif => "Animated GIF",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            TimelapseFormat::PngSequence => "png",
+            TimelapseFormat::Gif => "gif",
+        }
+    }
+
+    /// Pick the format matching a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|f| f.extension() == ext)
+    }
+}
+
+/// What to record and how.
+#[derive(Clone, Debug)]
+pub struct TimelapseSettings {
+    pub format: TimelapseFormat,
+    /// Total frames, including the first one taken before any step.
+    pub frames: u32,
+    /// Simulation steps between frames.
+    pub every: u32,
+    /// Playback rate of the animated formats.
+    pub fps: u32,
+}
+
+impl Default for TimelapseSettings {
+    fn default() -> Self {
+        Self {
+            format: TimelapseFormat::default(),
+            frames: 120,
+            every: 4,
+            fps: 30,
+        }
+    }
+}
+
+/// Destination that accepts frames one at a time.
+enum FrameSink {
+    Pngs(PathBuf),
+    Gif(gif::Encoder<BufWriter<File>>),
+}
+
+/// Captures a fixed world-space view while the simulation runs.
+pub struct Recorder {
+    pub path: PathBuf,
+    settings: TimelapseSettings,
+    sink: FrameSink,
+    view: Rect,
+    width: u32,
+    height: u32,
+    frames_written: u32,
+    steps_since_frame: u32,
+}
+
+impl Recorder {
+    /// Open the output at `path` and record `view` at `width`×`height`.
+    pub fn start(
+        path: &Path,
+        settings: TimelapseSettings,
+        view: Rect,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, ExportError> {
+        let sink = match settings.format {
+            TimelapseFormat::PngSequence => FrameSink::Pngs(path.to_path_buf()),
+            TimelapseFormat::Gif => {
+                let (w, h) = match (u16::try_from(width), u16::try_from(height)) {
+                    (Ok(w), Ok(h)) => (w, h),
+                    _ => {
+                        return Err(ExportError::Encode(format!(
+                            "GIF frames are limited to 65535 px, got {width}×{height}"
+                        )))
+                    }
+                };
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = gif::Encoder::new(file, w, h, &[])
+                    .map_err(|e| ExportError::Encode(e.to_string()))?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(|e| ExportError::Encode(e.to_string()))?;
+                FrameSink::Gif(encoder)
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            settings,
+            sink,
+            view,
+            width,
+            height,
+            frames_written: 0,
+            steps_since_frame: 0,
+        })
+    }
+
+    pub fn frames_written(&self) -> u32 {
+        self.frames_written
+    }
+
+    pub fn frames_total(&self) -> u32 {
+        self.settings.frames
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frames_written >= self.settings.frames
+    }
+
+    /// Call after every simulation step (and once before the first); writes
+    /// a frame when one is due. Returns `true` once all frames are written.
+    pub fn on_step(&mut self, scene: &ExportScene) -> Result<bool, ExportError> {
+        if self.is_done() {
+            return Ok(true);
+        }
+        if self.frames_written > 0 {
+            self.steps_since_frame += 1;
+            if self.steps_since_frame < self.settings.every.max(1) {
+                return Ok(false);
+            }
+        }
+        self.steps_since_frame = 0;
+        self.capture(scene)?;
+        Ok(self.is_done())
+    }
+
+    /// Render and write one frame right now.
+    fn capture(&mut self, scene: &ExportScene) -> Result<(), ExportError> {
+        let image = render_scene(scene, self.view, self.width, self.height);
+        match &mut self.sink {
+            FrameSink::Pngs(base) => {
+                image.write_png(&numbered_path(base, self.frames_written))?;
+            }
+            FrameSink::Gif(encoder) => {
+                let mut rgba = image.to_rgba8();
+                let mut frame = gif::Frame::from_rgba_speed(
+                    self.width as u16,
+                    self.height as u16,
+                    &mut rgba,
+                    GIF_QUANTIZE_SPEED,
+                );
+                // GIF delays are in hundredths of a second
+                frame.delay = (100 / self.settings.fps.clamp(1, 100)) as u16;
+                encoder
+                    .write_frame(&frame)
+                    .map_err(|e| ExportError::Encode(e.to_string()))?;
+            }
+        }
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    /// Flush the output; a recording can be finished early.
+    pub fn finish(self) -> Result<u32, ExportError> {
+        if let FrameSink::Gif(encoder) = self.sink {
+            encoder
+                .into_inner()
+                .map_err(|e| ExportError::Encode(e.to_string()))?;
+        }
+        Ok(self.frames_written)
+    }
+}
+
+/// `dir/name.png` → `dir/name_0007.png`.
+fn numbered_path(base: &Path, index: u32) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("frame");
+    base.with_file_name(format!("{stem}_{index:04}.png"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use eframe::egui::{Color32, Pos2};
+
+    use super::*;
+    use crate::app::layers::Layer;
+
+    /// Empty scratch directory for one test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("timelapse_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Record three frames two steps apart; returns the frames written.
+    fn record(path: &Path, format: TimelapseFormat) -> u32 {
+        let settings = TimelapseSettings {
+            format,
+            frames: 3,
+            every: 2,
+            fps: 10,
+        };
+        let view = Rect::from_min_max(Pos2::ZERO, Pos2::new(16.0, 16.0));
+        let mut recorder = Recorder::start(path, settings, view, 8, 8).unwrap();
+        let layers = [Layer::new(0, "Layer 1")];
+        let scene = ExportScene {
+            layers: &layers,
+            background: Color32::from_rgb(40, 80, 120),
+            base_size: 2.0,
+        };
+        let mut steps = 0;
+        while !recorder.on_step(&scene).unwrap() {
+            steps += 1;
+        }
+        assert_eq!(steps, 4);
+        // Further steps don't add frames
+        assert!(recorder.on_step(&scene).unwrap());
+        recorder.finish().unwrap()
+    }
+
+    #[test]
+    fn png_sequences_have_one_file_per_frame() {
+        let dir = scratch("png");
+        assert_eq!(record(&dir.join("grow.png"), TimelapseFormat::PngSequence), 3);
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["grow_0000.png", "grow_0001.png", "grow_0002.png"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gifs_hold_every_frame() {
+        let dir = scratch("gif");
+        let path = dir.join("grow.gif");
+        assert_eq!(record(&path, TimelapseFormat::Gif), 3);
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (8, 8));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,22 +1,308 @@
 // app/history.rs
 
-/// Minimal history/undo manager stub.
-///
-/// This will be expanded later to store snapshots or operations.
-pub struct History;
+use eframe::egui::Color32;
+
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::crystal::StrokeData;
+use crate::app::brushes::drip::Drip;
+use crate::app::layers::Layer;
+use crate::app::utils::math::Affine;
+
+/// Maximum number of undo steps kept.
+const MAX_ENTRIES: usize = 128;
+
+/// Rough cap on stored canvas elements (segments + blots) across all entries.
+const MAX_ELEMENTS: usize = 250_000;
+
+/// A single reversible canvas operation. `layer` fields are layer ids.
+#[derive(Clone)]
+pub enum Operation {
+    /// A crystal stroke was committed at `index` in the layer's `strokes`.
+    StrokeCommitted { layer: u32, index: usize, stroke: StrokeData },
+
+    /// A drip stroke was committed at `index` in the layer's `drips`.
+    DripCommitted { layer: u32, index: usize, drip: Drip },
+
+    /// A run of blots was appended starting at `start` in the layer's `blots`.
+    BlotsAdded { layer: u32, start: usize, blots: Vec<Blot> },
+
+    /// The canvas background changed.
+    CanvasColorChanged { before: Color32, after: Color32 },
+
+    /// A layer was cleared.
+    Destroyed {
+        layer: u32,
+        strokes: Vec<StrokeData>,
+        blots: Vec<Blot>,
+        drips: Vec<Drip>,
+    },
+
+    /// Strokes and blots of a layer were changed in place, by erasing,
+    /// pasting or deleting or duplicating a selection; both versions are
+    /// kept. A paste also appends `drips_added` at `drips_start`.
+    Edited {
+        layer: u32,
+        strokes_before: Vec<StrokeData>,
+        blots_before: Vec<Blot>,
+        strokes_after: Vec<StrokeData>,
+        blots_after: Vec<Blot>,
+        drips_start: usize,
+        drips_added: Vec<Drip>,
+    },
+
+    /// The strokes and blots at the given indices of a layer were moved,
+    /// scaled or rotated by `transform`.
+    Transformed {
+        layer: u32,
+        strokes: Vec<usize>,
+        blots: Vec<usize>,
+        transform: Affine,
+    },
+
+    /// A layer was inserted at `index` in the layer stack.
+    LayerAdded { index: usize, layer: Box<Layer> },
+
+    /// The layer at `index` was deleted.
+    LayerRemoved { index: usize, layer: Box<Layer> },
+
+    /// A layer moved from position `from` to `to`.
+    LayerMoved { from: usize, to: usize },
+
+    /// The layer at `index` was merged into the one below it; `lower` is
+    /// that layer as it was before the merge.
+    LayersMerged { index: usize, upper: Box<Layer>, lower: Box<Layer> },
+}
+
+impl Operation {
+    /// Whether undoing or redoing this restores `layer`'s strokes wholesale.
+    fn snapshots(&self, layer: u32) -> bool {
+        match self {
+            Operation::Destroyed { layer: l, .. } | Operation::Edited { layer: l, .. } => {
+                *l == layer
+            }
+            _ => false,
+        }
+    }
+
+    /// Approximate memory weight, counted in canvas elements.
+    fn cost(&self) -> usize {
+        match self {
+            Operation::StrokeCommitted { stroke, .. } => stroke.segments.len().max(1),
+            Operation::DripCommitted { drip, .. } => drip.path.len() + drip.runs.len(),
+            Operation::BlotsAdded { blots, .. } => blots.len().max(1),
+            Operation::CanvasColorChanged { .. } | Operation::LayerMoved { .. } => 1,
+            Operation::Destroyed { strokes, blots, drips, .. } => {
+                strokes.iter().map(|s| s.segments.len()).sum::<usize>()
+                    + drips.iter().map(|d| d.path.len() + d.runs.len()).sum::<usize>()
+                    + blots.len()
+                    + 1
+            }
+            Operation::Edited {
+                strokes_before,
+                blots_before,
+                strokes_after,
+                blots_after,
+                drips_added,
+                ..
+            } => {
+                strokes_before
+                    .iter()
+                    .chain(strokes_after)
+                    .map(|s| s.segments.len())
+                    .sum::<usize>()
+                    + blots_before.len()
+                    + blots_after.len()
+                    + drips_added.iter().map(|d| d.path.len() + d.runs.len()).sum::<usize>()
+                    + 1
+            }
+            Operation::Transformed { strokes, blots, .. } => strokes.len() + blots.len() + 1,
+            Operation::LayerAdded { layer, .. } | Operation::LayerRemoved { layer, .. } => {
+                layer.element_count() + 1
+            }
+            Operation::LayersMerged { upper, lower, .. } => {
+                upper.element_count() + lower.element_count() + 1
+            }
+        }
+    }
+}
+
+/// Command-log undo/redo manager with bounded memory.
+pub struct History {
+    undo_stack: Vec<Operation>,
+    redo_stack: Vec<Operation>,
+    /// Set while a canvas color edit is in progress, so the changes of one
+    /// picker drag merge into a single step.
+    color_edit: bool,
+}
 
 impl History {
     pub fn new() -> Self {
-        Self
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            color_edit: false,
+        }
+    }
+
+    /// Record a freshly applied operation. Clears the redo stack.
+    pub fn push(&mut self, op: Operation) {
+        self.redo_stack.clear();
+
+        // Coalesce the changes of one color-picker drag into one step.
+        if let Operation::CanvasColorChanged { after, .. } = &op {
+            if let Some(Operation::CanvasColorChanged { after: prev_after, .. }) = self
+                .undo_stack
+                .last_mut()
+                .filter(|_| self.color_edit)
+            {
+                *prev_after = *after;
+                return;
+            }
+        }
+
+        self.color_edit = matches!(op, Operation::CanvasColorChanged { .. });
+        self.undo_stack.push(op);
+        self.trim();
     }
 
-    /// Placeholder for later: push a snapshot or operation.
-    pub fn push(&mut self) {
-        // no-op for now
+    /// Whether a canvas color change now would join the previous one's undo
+    /// step.
+    pub fn in_color_edit(&self) -> bool {
+        self.color_edit
     }
 
-    /// Placeholder for undo functionality.
-    pub fn undo(&mut self) {
-        // no-op for now
+    /// End the canvas color edit in progress; the next change starts a new
+    /// undo step.
+    pub fn end_color_edit(&mut self) {
+        self.color_edit = false;
+    }
+
+    /// Pop the most recent operation so the caller can revert it.
+    pub fn undo(&mut self) -> Option<Operation> {
+        self.color_edit = false;
+        let op = self.undo_stack.pop()?;
+        self.redo_stack.push(op.clone());
+        Some(op)
+    }
+
+    /// Pop the most recently undone operation so the caller can re-apply it.
+    pub fn redo(&mut self) -> Option<Operation> {
+        self.color_edit = false;
+        let op = self.redo_stack.pop()?;
+        self.undo_stack.push(op.clone());
+        Some(op)
+    }
+
+    /// Strokes at the (ascending) `removed` indices of `layer` were dropped
+    /// from the canvas outside of the history, e.g. after decaying to nothing.
+    /// Shifts later stroke indices down and forgets undo steps for the
+    /// removed strokes, which are no longer there to undo. Steps on the far
+    /// side of a snapshot of the layer (clearing, editing) refer to the
+    /// snapshot's strokes and are left alone.
+    pub fn strokes_pruned(&mut self, layer: u32, removed: &[usize]) {
+        let shift = |index: usize| removed.iter().filter(|&&r| r < index).count();
+
+        let mut i = self.undo_stack.len();
+        while i > 0 {
+            i -= 1;
+            match &mut self.undo_stack[i] {
+                Operation::StrokeCommitted { layer: l, index, .. } if *l == layer => {
+                    if removed.contains(index) {
+                        self.undo_stack.remove(i);
+                    } else {
+                        *index -= shift(*index);
+                    }
+                }
+                Operation::Transformed { layer: l, strokes, .. } if *l == layer => {
+                    remap_pruned(strokes, removed);
+                }
+                op if op.snapshots(layer) => break,
+                _ => {}
+            }
+        }
+        for op in self.redo_stack.iter_mut().rev() {
+            match op {
+                Operation::StrokeCommitted { layer: l, index, .. } if *l == layer => {
+                    *index -= shift(*index);
+                }
+                Operation::Transformed { layer: l, strokes, .. } if *l == layer => {
+                    remap_pruned(strokes, removed);
+                }
+                op if op.snapshots(layer) => break,
+                _ => {}
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Drop the oldest entries until both limits are respected.
+    fn trim(&mut self) {
+        let mut total: usize = self.undo_stack.iter().map(Operation::cost).sum();
+
+        while self.undo_stack.len() > 1
+            && (self.undo_stack.len() > MAX_ENTRIES || total > MAX_ELEMENTS)
+        {
+            let dropped = self.undo_stack.remove(0);
+            total -= dropped.cost();
+        }
+    }
+}
+
+/// Drop the `removed` (ascending) stroke indices from `indices` and shift the
+/// rest down to match.
+pub fn remap_pruned(indices: &mut Vec<usize>, removed: &[usize]) {
+    indices.retain(|i| removed.binary_search(i).is_err());
+    for i in indices.iter_mut() {
+        *i -= removed.partition_point(|&r| r < *i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_change(history: &mut History, before: u8, after: u8) {
+        history.push(Operation::CanvasColorChanged {
+            before: Color32::from_gray(before),
+            after: Color32::from_gray(after),
+        });
+    }
+
+    #[test]
+    fn color_drag_is_one_step() {
+        let mut history = History::new();
+        color_change(&mut history, 0, 10);
+        color_change(&mut history, 10, 20);
+        color_change(&mut history, 20, 30);
+
+        let Some(Operation::CanvasColorChanged { before, after }) = history.undo() else {
+            panic!("expected a color change");
+        };
+        assert_eq!((before, after), (Color32::from_gray(0), Color32::from_gray(30)));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn separate_color_picks_undo_separately() {
+        let mut history = History::new();
+        color_change(&mut history, 0, 10);
+        history.end_color_edit();
+        color_change(&mut history, 10, 20);
+
+        assert!(matches!(
+            history.undo(),
+            Some(Operation::CanvasColorChanged { before, .. }) if before == Color32::from_gray(10)
+        ));
+        assert!(matches!(
+            history.undo(),
+            Some(Operation::CanvasColorChanged { before, .. }) if before == Color32::from_gray(0)
+        ));
     }
 }
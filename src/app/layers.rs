@@ -0,0 +1,202 @@
+// app/layers.rs
+//! Canvas layers: each owns its strokes, drips and blots and is composited
+//! bottom to top.
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::blend::BlendMode;
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::crystal::StrokeData;
+use crate::app::brushes::drip::Drip;
+use crate::app::spatial::SceneIndex;
+
+/// On/off setting of a layer, toggled from the layers panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerFlag {
+    Visible,
+    Locked,
+    Grow,
+}
+
+/// One layer of artwork.
+#[derive(Serialize, Deserialize)]
+pub struct Layer {
+    /// Stable identifier; history entries refer to layers by id, not position.
+    pub id: u32,
+    pub name: String,
+    pub visible: bool,
+    /// Locked layers ignore painting and clearing.
+    pub locked: bool,
+    pub opacity: f32,
+    pub blend: BlendMode,
+    /// Whether crystal strokes on this layer grow while the simulation runs.
+    pub grow: bool,
+
+    pub strokes: Vec<StrokeData>,
+    pub blots: Vec<Blot>,
+    pub drips: Vec<Drip>,
+
+    /// Spatial lookup over this layer's strokes and blots; rebuilt on demand.
+    #[serde(skip, default = "SceneIndex::new")]
+    pub index: SceneIndex,
+}
+
+impl Clone for Layer {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            name: self.name.clone(),
+            visible: self.visible,
+            locked: self.locked,
+            opacity: self.opacity,
+            blend: self.blend,
+            grow: self.grow,
+            strokes: self.strokes.clone(),
+            blots: self.blots.clone(),
+            drips: self.drips.clone(),
+            index: SceneIndex::new(),
+        }
+    }
+}
+
+impl Layer {
+    pub fn new(id: u32, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+            grow: true,
+            strokes: Vec::new(),
+            blots: Vec::new(),
+            drips: Vec::new(),
+            index: SceneIndex::new(),
+        }
+    }
+
+    /// The setting `flag` stands for.
+    pub fn flag_mut(&mut self, flag: LayerFlag) -> &mut bool {
+        match flag {
+            LayerFlag::Visible => &mut self.visible,
+            LayerFlag::Locked => &mut self.locked,
+            LayerFlag::Grow => &mut self.grow,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty() && self.blots.is_empty() && self.drips.is_empty()
+    }
+
+    /// Number of stored elements, counting each segment and drip point.
+    pub fn element_count(&self) -> usize {
+        self.strokes.iter().map(|s| s.segments.len()).sum::<usize>()
+            + self.drips.iter().map(|d| d.path.len() + d.runs.len()).sum::<usize>()
+            + self.blots.len()
+    }
+
+    /// Append everything from `upper` on top of this layer's content, baking
+    /// its opacity into the elements. Its blend mode is dropped.
+    pub fn absorb(&mut self, mut upper: Layer) {
+        if upper.opacity < 1.0 {
+            let fade = upper.opacity.clamp(0.0, 1.0);
+            for s in &mut upper.strokes {
+                s.color = s.color.gamma_multiply(fade);
+            }
+            for d in &mut upper.drips {
+                d.color = d.color.gamma_multiply(fade);
+            }
+            for b in &mut upper.blots {
+                b.opacity *= fade;
+            }
+        }
+
+        self.strokes.extend(upper.strokes);
+        self.blots.extend(upper.blots);
+        self.drips.extend(upper.drips);
+        self.index.invalidate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::{Color32, Pos2};
+
+    use crate::app::state::AppState;
+
+    fn blot(x: f32) -> Blot {
+        Blot {
+            pos: Pos2::new(x, 0.0),
+            radius: 4.0,
+            color: Color32::WHITE,
+            softness: 0.0,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    /// Two layers with a blot each; the upper one is half transparent and
+    /// active.
+    fn two_layers() -> AppState {
+        let mut state = AppState::without_user_config();
+        state.layers[0].blots.push(blot(0.0));
+        state.add_layer();
+        state.layers[1].blots.push(blot(10.0));
+        state.layers[1].opacity = 0.5;
+        state
+    }
+
+    /// Name, opacity and blot positions of each layer, bottom to top.
+    fn stack(state: &AppState) -> Vec<(String, f32, Vec<f32>)> {
+        let xs = |l: &Layer| l.blots.iter().map(|b| b.pos.x).collect();
+        state.layers.iter().map(|l| (l.name.clone(), l.opacity, xs(l))).collect()
+    }
+
+    #[test]
+    fn merging_down_bakes_the_upper_opacity() {
+        let mut state = two_layers();
+        state.merge_active_down();
+
+        assert_eq!(state.layers.len(), 1);
+        assert_eq!(state.active_layer, 0);
+        let opacities: Vec<f32> = state.layers[0].blots.iter().map(|b| b.opacity).collect();
+        assert_eq!(opacities, [1.0, 0.5]);
+    }
+
+    #[test]
+    fn undoing_a_merge_restores_both_layers() {
+        let mut state = two_layers();
+        let before = stack(&state);
+        state.merge_active_down();
+        let merged = stack(&state);
+
+        state.undo();
+        assert_eq!(stack(&state), before);
+        assert_eq!(state.active_layer, 1);
+        state.redo();
+        assert_eq!(stack(&state), merged);
+    }
+
+    #[test]
+    fn undoing_a_delete_puts_the_layer_back_in_place() {
+        let mut state = two_layers();
+        state.add_layer();
+        state.select_layer(1);
+        let before = stack(&state);
+
+        state.delete_active_layer();
+        assert_eq!(state.layers.len(), 2);
+        assert_eq!(state.layers[1].name, before[2].0);
+        state.undo();
+        assert_eq!(stack(&state), before);
+
+        // The last layer stays
+        state.select_layer(0);
+        state.delete_active_layer();
+        state.delete_active_layer();
+        state.delete_active_layer();
+        assert_eq!(state.layers.len(), 1);
+    }
+}
@@ -1,6 +1,19 @@
 pub mod state;
 pub mod history;
+pub mod layers;
 pub mod painter;
+pub mod blend;
+pub mod camera;
+pub mod simulation;
+pub mod document;
+pub mod cli;
+pub mod clipboard;
+pub mod presets;
+pub mod replay;
+pub mod export;
+pub mod spatial;
+pub mod selection;
+pub mod ribbon;
 
 pub mod brushes;
 pub mod ui;
@@ -1,10 +1,56 @@
 // app/painter.rs
 use eframe::egui::{
-    self, Align2, Color32, FontId, Pos2, Rect, Stroke,
+    self, Color32, Mesh, Pos2, Rect, Shape, Stroke, Vec2,
 };
+use eframe::emath::TSTransform;
 
 use crate::app::brushes::crystal::StrokeData;
 use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::drip::Drip;
+use crate::app::blend::BlendMode;
+use crate::app::layers::Layer;
+use crate::app::ribbon;
+use crate::app::selection::{self, Selection, HANDLE_SIZE};
+use crate::app::spatial::SceneIndex;
+
+/// Extra margin around the viewport when culling, covering geometry that
+/// moved since the index was last rebuilt.
+const CULL_MARGIN: f32 = 16.0;
+
+/// Ring stroke used to feather the edge of a soft blot.
+pub struct BlotHalo {
+    pub radius: f32,
+    pub width: f32,
+    pub color: Color32,
+}
+
+/// Layer opacity and blend mode applied to every shape of a layer.
+#[derive(Clone, Copy)]
+pub struct Tint {
+    pub opacity: f32,
+    pub blend: BlendMode,
+}
+
+impl Tint {
+    /// Elements with their own blend mode override the layer's.
+    fn with_element(self, blend: BlendMode) -> Self {
+        if blend == BlendMode::Normal {
+            self
+        } else {
+            Self { blend, ..self }
+        }
+    }
+
+    fn apply(self, color: Color32) -> Color32 {
+        self.blend.live_color(color.gamma_multiply(self.opacity))
+    }
+}
+
+/// Outline color of the selection box, its handles and the selected elements.
+const SELECTION_COLOR: Color32 = Color32::from_rgb(90, 200, 255);
+
+/// Outline color of the containment bounds.
+const BOUNDS_COLOR: Color32 = Color32::from_rgba_premultiplied(120, 120, 120, 120);
 
 /// Global painter for all canvas elements.
 pub struct CanvasPainter;
@@ -19,66 +65,168 @@ impl CanvasPainter {
         painter.rect_filled(rect, 0.0, canvas_color);
     }
 
-    /// Paint all crystal-type strokes.
+    /// Outline the world rectangle growth is contained in. The line stays
+    /// one point wide at any zoom.
+    pub fn paint_bounds(painter: &egui::Painter, to_screen: TSTransform, bounds: Rect) {
+        let line = Stroke::new(1.0, BOUNDS_COLOR);
+        painter.rect_stroke(to_screen.mul_rect(bounds), 0.0, line, egui::StrokeKind::Middle);
+    }
+
+    /// World-space area covered by the painter's clip rect, plus the cull margin.
+    fn world_view(painter: &egui::Painter, to_screen: TSTransform) -> Rect {
+        to_screen
+            .inverse()
+            .mul_rect(painter.clip_rect().expand(CULL_MARGIN))
+    }
+
+    /// Paint one layer's strokes, drips and blots, faded by its opacity.
+    /// Blend modes are approximated per shape, see [`BlendMode::live_color`].
+    pub fn paint_layer(
+        painter: &egui::Painter,
+        to_screen: TSTransform,
+        layer: &Layer,
+        base_size: f32,
+    ) {
+        let tint = Tint {
+            opacity: layer.opacity.clamp(0.0, 1.0),
+            blend: layer.blend,
+        };
+        if tint.opacity <= 0.0 {
+            return;
+        }
+        Self::paint_strokes(painter, to_screen, &layer.strokes, &layer.index, base_size, tint);
+        Self::paint_drips(painter, to_screen, &layer.drips, tint);
+        Self::paint_blots(painter, to_screen, &layer.blots, &layer.index, tint);
+    }
+
+    /// Paint all crystal-type strokes reaching into the painter's clip rect,
+    /// each run of connected segments as one filled ribbon.
     pub fn paint_strokes(
         painter: &egui::Painter,
+        to_screen: TSTransform,
         strokes: &[StrokeData],
+        index: &SceneIndex,
         base_size: f32,
+        tint: Tint,
     ) {
-        for stroke in strokes {
-            for seg in &stroke.segments {
-                painter.line_segment(
-                    [seg.start, seg.end],
-                    Stroke::new(
-                        stroke.thickness.unwrap_or(base_size),
-                        stroke.color,
-                    ),
-                );
+        let view = Self::world_view(painter, to_screen);
+        let mut visible: Vec<usize> = index.segments_in(view).iter().map(|r| r.stroke).collect();
+        visible.sort_unstable();
+        visible.dedup();
+
+        let feather = 1.0 / painter.pixels_per_point();
+        let mut mesh = Mesh::default();
+        for stroke in visible.into_iter().filter_map(|i| strokes.get(i)) {
+            for ribbon in ribbon::ribbons(stroke, base_size) {
+                ribbon
+                    .mapped(|p| to_screen.mul_pos(p), to_screen.scaling)
+                    .tessellate(&mut mesh, feather, |opacity| {
+                        tint.apply(stroke.color.gamma_multiply(opacity))
+                    });
             }
         }
+        if !mesh.is_empty() {
+            painter.add(Shape::mesh(mesh));
+        }
     }
 
-    /// Paint blotter circles.
-    pub fn paint_blots(painter: &egui::Painter, blots: &[Blot]) {
-        for b in blots {
-            // Apply opacity
-            let alpha = (b.opacity * 255.0) as u8;
-            let col = Color32::from_rgba_unmultiplied(
-                b.color.r(),
-                b.color.g(),
-                b.color.b(),
-                alpha,
-            );
+    /// Paint blotter circles inside the painter's clip rect.
+    pub fn paint_blots(
+        painter: &egui::Painter,
+        to_screen: TSTransform,
+        blots: &[Blot],
+        index: &SceneIndex,
+        tint: Tint,
+    ) {
+        let view = Self::world_view(painter, to_screen);
+        let zoom = to_screen.scaling;
+
+        for b in index.blots_in(view, blots).into_iter().map(|i| &blots[i]) {
+            let center = to_screen.mul_pos(b.pos);
+            let tint = tint.with_element(b.blend);
 
             // ---- SINGLE CIRCLE SHAPE ----
             // Main fill
-            painter.circle_filled(b.pos, b.radius, col);
+            painter.circle_filled(center, b.radius * zoom, tint.apply(Self::blot_fill(b)));
 
             // Feathered halo if softness > 0.01
-            if b.softness > 0.001 {
-                let feather_alpha =
-                    (alpha as f32 * b.softness * 0.5) as u8;
-
+            if let Some(halo) = Self::blot_halo(b) {
                 painter.circle_stroke(
-                    b.pos,
-                    b.radius * (1.0 + b.softness * 0.15),
-                    Stroke::new(
-                        b.radius * 0.25 * b.softness,
-                        Color32::from_rgba_unmultiplied(
-                            b.color.r(),
-                            b.color.g(),
-                            b.color.b(),
-                            feather_alpha,
-                        ),
-                    ),
+                    center,
+                    halo.radius * zoom,
+                    Stroke::new(halo.width * zoom, tint.apply(halo.color)),
                 );
             }
         }
     }
 
-    /// Paint live preview line while dragging.
+    /// Paint drip strokes and the tapered runs flowing off them.
+    pub fn paint_drips(
+        painter: &egui::Painter,
+        to_screen: TSTransform,
+        drips: &[Drip],
+        tint: Tint,
+    ) {
+        let view = Self::world_view(painter, to_screen);
+        let zoom = to_screen.scaling;
+
+        for drip in drips {
+            let color = tint.apply(drip.color);
+            let stroke = Stroke::new(drip.thickness * zoom, color);
+            for w in drip.path.windows(2) {
+                painter.line_segment([to_screen.mul_pos(w[0]), to_screen.mul_pos(w[1])], stroke);
+            }
+
+            for run in &drip.runs {
+                let origin = run.points.first().copied().unwrap_or(run.head());
+                if !view.intersects(Rect::from_two_pos(origin, run.head())) {
+                    continue;
+                }
+                for (a, b, width) in run.pieces(drip.thickness) {
+                    painter.line_segment(
+                        [to_screen.mul_pos(a), to_screen.mul_pos(b)],
+                        Stroke::new(width * zoom, color),
+                    );
+                }
+                let bead = run.head_radius(drip.thickness);
+                if bead > 0.0 {
+                    painter.circle_filled(to_screen.mul_pos(run.head()), bead * zoom, color);
+                }
+            }
+        }
+    }
+
+    /// Fill color of a blot with its opacity applied.
+    pub fn blot_fill(b: &Blot) -> Color32 {
+        let alpha = (b.opacity * 255.0) as u8;
+        Color32::from_rgba_unmultiplied(b.color.r(), b.color.g(), b.color.b(), alpha)
+    }
+
+    /// Feathered ring drawn around soft blots, shared with the offscreen renderers.
+    pub fn blot_halo(b: &Blot) -> Option<BlotHalo> {
+        if b.softness <= 0.001 {
+            return None;
+        }
+
+        let alpha = (b.opacity * 255.0) as u8;
+        let feather_alpha = (alpha as f32 * b.softness * 0.5) as u8;
+
+        Some(BlotHalo {
+            radius: b.radius * (1.0 + b.softness * 0.15),
+            width: b.radius * 0.25 * b.softness,
+            color: Color32::from_rgba_unmultiplied(
+                b.color.r(),
+                b.color.g(),
+                b.color.b(),
+                feather_alpha,
+            ),
+        })
+    }
+
+    /// Paint live preview line while dragging. The line stays one point wide at any zoom.
     pub fn paint_active_path(
         painter: &egui::Painter,
+        to_screen: TSTransform,
         pts: &[Pos2],
     ) {
         if pts.len() < 2 {
@@ -86,11 +234,50 @@ impl CanvasPainter {
         }
 
         for w in pts.windows(2) {
-            painter.line_segment([w[0], w[1]], Stroke::new(1.0, Color32::WHITE));
+            painter.line_segment(
+                [to_screen.mul_pos(w[0]), to_screen.mul_pos(w[1])],
+                Stroke::new(1.0, Color32::WHITE),
+            );
+        }
+    }
+
+    /// Outline the selected elements and draw the selection box with its
+    /// scale handles and rotate knob. Lines stay one point wide at any zoom.
+    pub fn paint_selection(
+        painter: &egui::Painter,
+        to_screen: TSTransform,
+        layer: &Layer,
+        selection: &Selection,
+    ) {
+        let Some(bounds) = selection.bounds(layer) else {
+            return;
+        };
+        let line = Stroke::new(1.0, SELECTION_COLOR);
+
+        for stroke in selection.strokes.iter().filter_map(|&i| layer.strokes.get(i)) {
+            for seg in &stroke.segments {
+                let ends = [to_screen.mul_pos(seg.start), to_screen.mul_pos(seg.end)];
+                painter.line_segment(ends, line);
+            }
+        }
+        for b in selection.blots.iter().filter_map(|&i| layer.blots.get(i)) {
+            painter.circle_stroke(to_screen.mul_pos(b.pos), b.radius * to_screen.scaling, line);
+        }
+
+        let screen = to_screen.mul_rect(bounds);
+        painter.rect_stroke(screen, 0.0, line, egui::StrokeKind::Middle);
+
+        let knob = to_screen.mul_pos(selection::rotate_knob(bounds, to_screen.scaling));
+        painter.line_segment([screen.center_top(), knob], line);
+        painter.circle_filled(knob, HANDLE_SIZE * 0.5, SELECTION_COLOR);
+        for corner in selection::corners(bounds) {
+            let handle = Rect::from_center_size(to_screen.mul_pos(corner), Vec2::splat(HANDLE_SIZE));
+            painter.rect_filled(handle, 0.0, SELECTION_COLOR);
         }
     }
 
     /// Paint overlay with debug / stats.
+    #[allow(unused_variables)]
     pub fn paint_overlay(
         painter: &egui::Painter,
         rect: Rect,
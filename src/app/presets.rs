@@ -0,0 +1,231 @@
+// app/presets.rs
+//! Named brush presets, kept in the user config dir and shareable as packs.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::brushes::blotter::Blotter;
+use crate::app::brushes::blotter_props::BlotterProps;
+use crate::app::brushes::crystal::CrystalBrush;
+use crate::app::brushes::crystal_props::CrystalProps;
+use crate::app::brushes::drip::DripBrush;
+use crate::app::brushes::drip_props::DripProps;
+use crate::app::brushes::registry::BrushRegistry;
+use crate::app::brushes::BrushKind;
+
+/// File extension used by the pack import/export dialogs.
+pub const PACK_EXTENSION: &str = "json";
+
+/// Application folder inside the platform config dir.
+const CONFIG_DIR_NAME: &str = "crystal_painter";
+
+/// The user's own library inside `CONFIG_DIR_NAME`.
+const LIBRARY_FILE: &str = "presets.json";
+
+/// Errors raised while reading or writing presets.
+#[derive(Debug)]
+pub enum PresetError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    NoConfigDir,
+    /// The user library failed to load, so it isn't overwritten.
+    NotLoaded,
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Io(e) => write!(f, "I/O error: {e}"),
+            PresetError::Parse(e) => write!(f, "invalid preset file: {e}"),
+            PresetError::NoConfigDir => write!(f, "no user config directory available"),
+            PresetError::NotLoaded => write!(
+                f,
+                "the preset library couldn't be read at startup; fix or remove {LIBRARY_FILE} to save presets"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<std::io::Error> for PresetError {
+    fn from(e: std::io::Error) -> Self {
+        PresetError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(e: serde_json::Error) -> Self {
+        PresetError::Parse(e)
+    }
+}
+
+/// A named set of brush properties.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Preset<P> {
+    pub name: String,
+    pub props: P,
+}
+
+/// Presets for every brush. The same layout is used for the user library
+/// and for shared packs.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetLibrary {
+    pub crystal: Vec<Preset<CrystalProps>>,
+    pub drip: Vec<Preset<DripProps>>,
+    pub blotter: Vec<Preset<BlotterProps>>,
+}
+
+impl PresetLibrary {
+    /// Location of the user library, if the platform has a config dir.
+    pub fn user_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(LIBRARY_FILE))
+    }
+
+    /// Read the user library. A missing file is an empty library.
+    pub fn load_user() -> Result<Self, PresetError> {
+        let path = Self::user_path().ok_or(PresetError::NoConfigDir)?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(&path)
+    }
+
+    /// Write the user library, creating the config folder if needed.
+    pub fn save_user(&self) -> Result<(), PresetError> {
+        let path = Self::user_path().ok_or(PresetError::NoConfigDir)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.save(&path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PresetError> {
+        let text = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PresetError> {
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Preset names for one brush, in library order.
+    pub fn names(&self, kind: BrushKind) -> Vec<String> {
+        match kind {
+            BrushKind::CRYSTAL => names(&self.crystal),
+            BrushKind::DRIP => names(&self.drip),
+            BrushKind::BLOTTER => names(&self.blotter),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Store the current properties of `kind` under `name`, replacing any
+    /// preset of the same name. Returns `false` for brushes without presets.
+    pub fn store(&mut self, kind: BrushKind, name: &str, brushes: &BrushRegistry) -> bool {
+        match kind {
+            BrushKind::CRYSTAL => brushes
+                .typed::<CrystalBrush>()
+                .map(|b| upsert(&mut self.crystal, name, b.props.clone()))
+                .is_some(),
+            BrushKind::DRIP => brushes
+                .typed::<DripBrush>()
+                .map(|b| upsert(&mut self.drip, name, b.props.clone()))
+                .is_some(),
+            BrushKind::BLOTTER => brushes
+                .typed::<Blotter>()
+                .map(|b| upsert(&mut self.blotter, name, b.props.clone()))
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// Load the preset `name` into the brush of `kind`. Returns `false` if
+    /// there is no such preset.
+    pub fn apply(&self, kind: BrushKind, name: &str, brushes: &mut BrushRegistry) -> bool {
+        match kind {
+            BrushKind::CRYSTAL => load_into(
+                &self.crystal,
+                name,
+                brushes.typed_mut::<CrystalBrush>().map(|b| &mut b.props),
+            ),
+            BrushKind::DRIP => load_into(
+                &self.drip,
+                name,
+                brushes.typed_mut::<DripBrush>().map(|b| &mut b.props),
+            ),
+            BrushKind::BLOTTER => load_into(
+                &self.blotter,
+                name,
+                brushes.typed_mut::<Blotter>().map(|b| &mut b.props),
+            ),
+            _ => false,
+        }
+    }
+
+    /// Remove the preset `name` of `kind`. Returns `false` if it didn't exist.
+    pub fn remove(&mut self, kind: BrushKind, name: &str) -> bool {
+        match kind {
+            BrushKind::CRYSTAL => remove(&mut self.crystal, name),
+            BrushKind::DRIP => remove(&mut self.drip, name),
+            BrushKind::BLOTTER => remove(&mut self.blotter, name),
+            _ => false,
+        }
+    }
+
+    /// Add every preset from `pack`; presets with a name already in the
+    /// library replace the existing one. Returns the number merged.
+    pub fn merge(&mut self, pack: PresetLibrary) -> usize {
+        let count = pack.count();
+        for p in pack.crystal {
+            upsert(&mut self.crystal, &p.name, p.props);
+        }
+        for p in pack.drip {
+            upsert(&mut self.drip, &p.name, p.props);
+        }
+        for p in pack.blotter {
+            upsert(&mut self.blotter, &p.name, p.props);
+        }
+        count
+    }
+
+    /// Total number of presets across all brushes.
+    pub fn count(&self) -> usize {
+        self.crystal.len() + self.drip.len() + self.blotter.len()
+    }
+}
+
+fn names<P>(list: &[Preset<P>]) -> Vec<String> {
+    list.iter().map(|p| p.name.clone()).collect()
+}
+
+fn load_into<P: Clone>(list: &[Preset<P>], name: &str, target: Option<&mut P>) -> bool {
+    match (list.iter().find(|p| p.name == name), target) {
+        (Some(preset), Some(props)) => {
+            *props = preset.props.clone();
+            true
+        }
+        _ => false,
+    }
+}
+
+fn upsert<P>(list: &mut Vec<Preset<P>>, name: &str, props: P) {
+    match list.iter_mut().find(|p| p.name == name) {
+        Some(existing) => existing.props = props,
+        None => list.push(Preset {
+            name: name.to_owned(),
+            props,
+        }),
+    }
+}
+
+fn remove<P>(list: &mut Vec<Preset<P>>, name: &str) -> bool {
+    let before = list.len();
+    list.retain(|p| p.name != name);
+    list.len() != before
+}
@@ -0,0 +1,424 @@
+// app/replay.rs
+//! Input recording and playback.
+//!
+//! A recording is a snapshot of the document when recording started plus the
+//! raw input that followed, each event stamped with the simulation step it
+//! happened on. Because the simulation is seeded and fixed-step, playing the
+//! events back on top of the snapshot reproduces the session exactly; playing
+//! them with other brush settings re-renders the same gestures differently.
+//!
+//! Layer stack edits (adding, deleting, moving, merging, switching layers and
+//! toggling visibility, lock and growth) are recorded like any other input,
+//! and so are canvas color changes, cuts and pastes; a paste carries the
+//! clipboard text with it. Brush setting changes and a layer's name, opacity
+//! and blend mode are not; playback uses the settings from the snapshot (or
+//! the current ones).
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use eframe::egui::{Color32, Pos2, Rect};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app::document::{Document, DocumentError};
+use crate::app::layers::LayerFlag;
+
+pub const REPLAY_EXTENSION: &str = "replay";
+
+/// Current replay file format; bump when events change incompatibly.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Errors raised while reading or writing a replay file.
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    Document(DocumentError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "I/O error: {e}"),
+            ReplayError::Parse(e) => write!(f, "invalid replay file: {e}"),
+            ReplayError::UnsupportedVersion(v) => write!(
+                f,
+                "replay version {v} is newer than supported version {REPLAY_VERSION}"
+            ),
+            ReplayError::Document(e) => write!(f, "invalid starting document: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(e: serde_json::Error) -> Self {
+        ReplayError::Parse(e)
+    }
+}
+
+impl From<DocumentError> for ReplayError {
+    fn from(e: DocumentError) -> Self {
+        ReplayError::Document(e)
+    }
+}
+
+/// Growth controls in effect; recorded whenever they change.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Controls {
+    pub paused: bool,
+    pub growth_speed: f32,
+    /// World rectangle growth was kept inside, if "Contain" was on.
+    pub contain: Option<Rect>,
+}
+
+/// One piece of recorded input.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum InputEvent {
+    PointerDown { pos: Pos2, pressure: Option<f32> },
+    PointerDrag { pos: Pos2, pressure: Option<f32> },
+    PointerUp,
+    /// Active brush, by `BrushKind` id.
+    Brush { kind: String },
+    Color { color: Color32 },
+    /// Canvas background; `continues` when the change joins the previous
+    /// one's undo step, as while dragging across the picker.
+    CanvasColor { color: Color32, continues: bool },
+    ActiveLayer { index: usize },
+    AddLayer,
+    DeleteLayer,
+    /// Active layer moved `offset` places up (+) or down (-) the stack.
+    MoveLayer { offset: isize },
+    MergeDown,
+    LayerFlag { index: usize, flag: LayerFlag, on: bool },
+    Controls(Controls),
+    Undo,
+    Redo,
+    Destroy,
+    ClearSelection,
+    DeleteSelection,
+    DuplicateSelection,
+    /// Removal of the copied elements; the clipboard itself isn't touched.
+    Cut,
+    /// Clipboard `text` holding a fragment, pasted centered on `at`.
+    Paste { text: String, at: Pos2 },
+}
+
+/// An event and the step (counted from the start of the recording) it
+/// happened before.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimedEvent {
+    pub step: u64,
+    #[serde(flatten)]
+    pub event: InputEvent,
+}
+
+/// A recorded session.
+#[derive(Clone, Serialize)]
+pub struct Recording {
+    pub version: u32,
+    /// Document as it was when recording started.
+    pub start: Document,
+    /// Steps the recording lasted.
+    pub steps: u64,
+    pub events: Vec<TimedEvent>,
+}
+
+impl Recording {
+    pub fn to_json(&self) -> Result<String, ReplayError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a replay, migrating its starting document like a project file.
+    pub fn from_json(text: &str) -> Result<Self, ReplayError> {
+        #[derive(Deserialize)]
+        struct Body {
+            version: u32,
+            steps: u64,
+            events: Vec<TimedEvent>,
+        }
+
+        let mut value: Value = serde_json::from_str(text)?;
+        let start = value.get_mut("start").map(Value::take).unwrap_or(Value::Null);
+        let body: Body = serde_json::from_value(value)?;
+        if body.version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(body.version));
+        }
+
+        Ok(Self {
+            version: REPLAY_VERSION,
+            start: Document::from_value(start)?,
+            steps: body.steps,
+            events: body.events,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// Collects input while a session is being recorded.
+pub struct InputRecorder {
+    start: Document,
+    start_step: u64,
+    events: Vec<TimedEvent>,
+
+    // last recorded values, so unchanged settings aren't repeated
+    brush: Option<String>,
+    color: Option<Color32>,
+    controls: Option<Controls>,
+}
+
+impl InputRecorder {
+    /// Start recording on top of `start`, taken at simulation step `step`.
+    pub fn new(start: Document, step: u64) -> Self {
+        Self {
+            start,
+            start_step: step,
+            events: Vec::new(),
+            brush: None,
+            color: None,
+            controls: None,
+        }
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Record `event` as happening at absolute simulation step `step`.
+    pub fn push(&mut self, step: u64, event: InputEvent) {
+        self.events.push(TimedEvent {
+            step: step.saturating_sub(self.start_step),
+            event,
+        });
+    }
+
+    /// Record the brush and color a stroke (or cut) starts with, if changed.
+    pub fn note_stroke_setup(&mut self, step: u64, brush: &str, color: Color32) {
+        if self.brush.as_deref() != Some(brush) {
+            self.brush = Some(brush.to_owned());
+            self.push(step, InputEvent::Brush { kind: brush.to_owned() });
+        }
+        if self.color != Some(color) {
+            self.color = Some(color);
+            self.push(step, InputEvent::Color { color });
+        }
+    }
+
+    /// Record the growth controls, if changed.
+    pub fn note_controls(&mut self, step: u64, controls: Controls) {
+        if self.controls != Some(controls) {
+            self.controls = Some(controls);
+            self.push(step, InputEvent::Controls(controls));
+        }
+    }
+
+    /// Close the recording at absolute simulation step `step`.
+    pub fn finish(self, step: u64) -> Recording {
+        Recording {
+            version: REPLAY_VERSION,
+            start: self.start,
+            steps: step.saturating_sub(self.start_step),
+            events: self.events,
+        }
+    }
+}
+
+/// Feeds a recording back step by step.
+pub struct Player {
+    events: Vec<TimedEvent>,
+    steps: u64,
+    start_step: u64,
+    next: usize,
+    /// Run the recording as fast as the per-frame step budget allows instead
+    /// of in real time.
+    pub fast: bool,
+    /// Containment from the last recorded controls.
+    pub contain: Option<Rect>,
+}
+
+impl Player {
+    /// Play `recording` starting at absolute simulation step `step`; its
+    /// starting document must already be applied.
+    pub fn new(recording: Recording, step: u64, fast: bool) -> Self {
+        Self {
+            events: recording.events,
+            steps: recording.steps,
+            start_step: step,
+            next: 0,
+            fast,
+            contain: None,
+        }
+    }
+
+    /// Events due before absolute simulation step `step` runs, in order.
+    pub fn due(&mut self, step: u64) -> Vec<InputEvent> {
+        let step = step.saturating_sub(self.start_step);
+        let end = self.events[self.next..]
+            .iter()
+            .position(|e| e.step > step)
+            .map_or(self.events.len(), |i| self.next + i);
+        let due = self.events[self.next..end]
+            .iter()
+            .map(|e| e.event.clone())
+            .collect();
+        self.next = end;
+        due
+    }
+
+    /// Whether the recorded duration has elapsed by absolute simulation step
+    /// `step`; only events stamped with the final step remain after that.
+    pub fn is_finished(&self, step: u64) -> bool {
+        step.saturating_sub(self.start_step) >= self.steps
+    }
+
+    /// Playback position as a fraction of the recording.
+    pub fn progress(&self, step: u64) -> f32 {
+        if self.steps == 0 {
+            return 1.0;
+        }
+        (step.saturating_sub(self.start_step) as f32 / self.steps as f32).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::{self, Pos2};
+
+    use super::*;
+    use crate::app::brushes::BrushKind;
+    use crate::app::clipboard::Fragment;
+    use crate::app::state::AppState;
+
+    fn stroke(state: &mut AppState, y: f32) {
+        state.pointer_down(Pos2::new(20.0, y));
+        for x in 1..8 {
+            state.pointer_drag(Pos2::new(20.0 + x as f32 * 12.0, y));
+            state.tick(None);
+        }
+        state.pointer_up();
+        state.tick(None);
+    }
+
+    /// Layers with their settings and elements, for comparing canvases.
+    fn layers(state: &AppState) -> String {
+        serde_json::to_string(&state.layers).unwrap()
+    }
+
+    fn replay(recording: Recording) -> AppState {
+        let mut state = AppState::without_user_config();
+        state.play(recording, true);
+        while state.player.is_some() {
+            state.tick(None);
+        }
+        state
+    }
+
+    #[test]
+    fn layer_edits_replay() {
+        let mut state = AppState::without_user_config();
+        state.paused = false;
+        state.start_input_recording();
+
+        stroke(&mut state, 20.0);
+        state.add_layer();
+        stroke(&mut state, 60.0);
+        state.add_layer();
+        stroke(&mut state, 100.0);
+        state.move_active_layer(-1);
+        state.set_layer_flag(0, LayerFlag::Grow, false);
+        state.set_layer_flag(2, LayerFlag::Visible, false);
+        state.select_layer(2);
+        state.merge_active_down();
+        state.select_layer(0);
+        state.set_layer_flag(0, LayerFlag::Locked, true);
+        stroke(&mut state, 140.0);
+        state.add_layer();
+        state.delete_active_layer();
+        for _ in 0..30 {
+            state.tick(None);
+        }
+
+        let recording = state.input_recorder.take().unwrap().finish(state.sim.step);
+        let recording = Recording::from_json(&recording.to_json().unwrap()).unwrap();
+        let replayed = replay(recording);
+        assert_eq!(replayed.layers.len(), 2);
+        assert_eq!(replayed.active_layer, state.active_layer);
+        assert_eq!(layers(&replayed), layers(&state));
+    }
+
+    #[test]
+    fn cuts_and_pastes_replay() {
+        let mut state = AppState::without_user_config();
+        state.paused = false;
+        state.start_input_recording();
+
+        stroke(&mut state, 20.0);
+        stroke(&mut state, 60.0);
+        let time = state.sim.time();
+        let text = Fragment::copy(state.active_layer(), None, time).to_json().unwrap();
+        state.add_layer();
+        state.paste(&text, Pos2::new(200.0, 200.0));
+        state.tick(None);
+
+        state.select_layer(0);
+        state.active_brush = BrushKind::SELECT;
+        state.pointer_down(Pos2::new(10.0, 10.0));
+        state.pointer_drag(Pos2::new(200.0, 40.0));
+        state.pointer_up();
+        state.cut(&egui::Context::default());
+        for _ in 0..30 {
+            state.tick(None);
+        }
+
+        let recording = state.input_recorder.take().unwrap().finish(state.sim.step);
+        let recording = Recording::from_json(&recording.to_json().unwrap()).unwrap();
+        let replayed = replay(recording);
+        assert_eq!(state.layers[0].strokes.len(), 1);
+        assert_eq!(state.layers[1].strokes.len(), 2);
+        assert_eq!(layers(&replayed), layers(&state));
+    }
+
+    #[test]
+    fn canvas_color_changes_replay_with_their_undo_steps() {
+        let mut state = AppState::without_user_config();
+        state.start_input_recording();
+
+        // One drag across the picker, then a separate pick
+        state.set_canvas_color(Color32::from_gray(10));
+        state.set_canvas_color(Color32::from_gray(20));
+        state.history.end_color_edit();
+        state.tick(None);
+        state.set_canvas_color(Color32::from_gray(30));
+        state.tick(None);
+        state.undo();
+        state.tick(None);
+
+        let recording = state.input_recorder.take().unwrap().finish(state.sim.step);
+        let recording = Recording::from_json(&recording.to_json().unwrap()).unwrap();
+        let mut replayed = replay(recording);
+        assert_eq!(state.canvas_bg, Color32::from_gray(20));
+        assert_eq!(replayed.canvas_bg, state.canvas_bg);
+
+        replayed.undo();
+        assert_eq!(replayed.canvas_bg, AppState::without_user_config().canvas_bg);
+    }
+}
@@ -0,0 +1,355 @@
+// app/ribbon.rs
+//! Crystal strokes as ribbons: runs of connected segments with a width and an
+//! opacity at every vertex. Each ribbon is filled as one shape with miter
+//! joins (rounded where the miter would be too long) and round caps, so the
+//! joints of a stroke stay seamless. Shared by the live painter and the
+//! offscreen renderers.
+
+use std::f32::consts::PI;
+
+use eframe::egui::{Color32, Mesh, Pos2, Vec2};
+
+use crate::app::brushes::crystal::StrokeData;
+
+/// Longest miter, in half-widths, before a join is rounded instead.
+pub const MITER_LIMIT: f32 = 2.0;
+
+/// Largest angle (radians) between the points of a round cap or join.
+const ARC_STEP: f32 = PI / 8.0;
+
+/// Segments shorter than this are left out of ribbons.
+const MIN_LENGTH: f32 = 1e-3;
+
+/// How close a segment must start to its parent's end to continue it.
+const JOIN_EPS: f32 = 1e-3;
+
+/// Centerline of a run of connected segments.
+#[derive(Clone, Debug, Default)]
+pub struct Ribbon {
+    pub points: Vec<Pos2>,
+    /// Full width at each point.
+    pub widths: Vec<f32>,
+    /// Segment opacity at each point.
+    pub opacities: Vec<f32>,
+}
+
+/// Split `stroke` into ribbons. A segment continues the ribbon of its parent
+/// when it is the first child of the same generation starting at the
+/// parent's end; side branches start ribbons of their own. Widths are scaled
+/// by the stroke's thickness, or `base_size` without one.
+pub fn ribbons(stroke: &StrokeData, base_size: f32) -> Vec<Ribbon> {
+    let thickness = stroke.thickness.unwrap_or(base_size);
+    let segments = &stroke.segments;
+
+    let mut next = vec![None; segments.len()];
+    let mut continues = vec![false; segments.len()];
+    for (i, seg) in segments.iter().enumerate() {
+        let Some(p) = seg.parent.map(|p| p as usize) else {
+            continue;
+        };
+        let Some(parent) = segments.get(p) else {
+            continue;
+        };
+        if next[p].is_none()
+            && parent.generation == seg.generation
+            && parent.end.distance(seg.start) < JOIN_EPS
+        {
+            next[p] = Some(i);
+            continues[i] = true;
+        }
+    }
+
+    let mut ribbons = Vec::new();
+    for head in (0..segments.len()).filter(|&i| !continues[i]) {
+        let mut ribbon = Ribbon::default();
+        let mut at = Some(head);
+        while let Some(i) = at {
+            let seg = &segments[i];
+            if seg.start.distance(seg.end) >= MIN_LENGTH {
+                match ribbon.points.len().checked_sub(1) {
+                    // Joint: the two segments meeting here share the vertex
+                    Some(last) => {
+                        let width = seg.width[0] * thickness;
+                        ribbon.widths[last] = (ribbon.widths[last] + width) * 0.5;
+                        ribbon.opacities[last] = (ribbon.opacities[last] + seg.opacity) * 0.5;
+                    }
+                    None => ribbon.push(seg.start, seg.width[0] * thickness, seg.opacity),
+                }
+                ribbon.push(seg.end, seg.width[1] * thickness, seg.opacity);
+            }
+            at = next[i];
+        }
+        if ribbon.points.len() >= 2 {
+            ribbons.push(ribbon);
+        }
+    }
+    ribbons
+}
+
+impl Ribbon {
+    pub fn push(&mut self, point: Pos2, width: f32, opacity: f32) {
+        self.points.push(point);
+        self.widths.push(width);
+        self.opacities.push(opacity);
+    }
+
+    /// The ribbon with `to_space` applied to its points and widths scaled by
+    /// `scale`, e.g. to go from canvas to screen coordinates.
+    pub fn mapped(&self, to_space: impl Fn(Pos2) -> Pos2, scale: f32) -> Ribbon {
+        Ribbon {
+            points: self.points.iter().map(|&p| to_space(p)).collect(),
+            widths: self.widths.iter().map(|w| w * scale).collect(),
+            opacities: self.opacities.clone(),
+        }
+    }
+
+    /// Append the filled ribbon to `mesh`. Edges fade out over `feather`
+    /// (one physical pixel, in the ribbon's units); parts thinner than that
+    /// are drawn `feather` wide and fainter instead. `color_at` gives the
+    /// color for a vertex opacity.
+    pub fn tessellate(&self, mesh: &mut Mesh, feather: f32, color_at: impl Fn(f32) -> Color32) {
+        let triangles = self.triangulate(feather);
+        let base = mesh.vertices.len() as u32;
+        for v in &triangles.vertices {
+            mesh.colored_vertex(v.pos, color_at(v.opacity).gamma_multiply(v.strength));
+        }
+        mesh.indices.extend(triangles.indices.iter().map(|i| base + i));
+    }
+
+    /// Triangles filling the ribbon, feathered as in [`Self::tessellate`].
+    /// They tile the ribbon without overlapping, so a translucent ribbon is
+    /// painted once everywhere, joints included.
+    pub fn triangulate(&self, feather: f32) -> RibbonMesh {
+        let mut mesh = RibbonMesh::default();
+        let n = self.points.len();
+        if n < 2 {
+            return mesh;
+        }
+
+        let normals = normals(&self.points);
+        let sections: Vec<Section> = (0..n)
+            .map(|i| {
+                let width = self.widths[i].max(0.0);
+                Section {
+                    center: self.points[i],
+                    radius: width.max(feather) * 0.5,
+                    opacity: self.opacities[i],
+                    strength: (width / feather).min(1.0),
+                }
+            })
+            .collect();
+        let lengths: Vec<f32> = self.points.windows(2).map(|w| w[0].distance(w[1])).collect();
+        let joins: Vec<Option<Join>> = (0..n)
+            .map(|i| {
+                (i > 0 && i < n - 1).then(|| {
+                    let reach = lengths[i - 1].min(lengths[i]) / (sections[i].radius + feather * 0.5);
+                    join(normals[i - 1], normals[i], reach)
+                })
+            })
+            .collect();
+
+        for (k, &normal) in normals.iter().enumerate() {
+            let ends = [k, k + 1].map(|i| {
+                let (left, right) = offsets(joins[i], normal);
+                (sections[i], left, right)
+            });
+            mesh.band(ends, feather);
+        }
+
+        // Round joins: a sector on the outside of the turn, spanning from
+        // the inner point both bands end on
+        for i in 1..n - 1 {
+            if let Some(Join::Round { inner, turn }) = joins[i] {
+                let (from, to) = if turn < 0.0 {
+                    (normals[i - 1], normals[i])
+                } else {
+                    (-normals[i - 1], -normals[i])
+                };
+                mesh.fan(sections[i], inner, [from, to], turn, feather);
+            }
+        }
+
+        // Round caps
+        mesh.fan(sections[0], Vec2::ZERO, [normals[0], -normals[0]], PI, feather);
+        let end = normals[n - 2];
+        mesh.fan(sections[n - 1], Vec2::ZERO, [end, -end], -PI, feather);
+        mesh
+    }
+}
+
+/// Triangles of a ribbon before coloring, see [`Ribbon::triangulate`].
+#[derive(Clone, Debug, Default)]
+pub struct RibbonMesh {
+    pub vertices: Vec<RibbonVertex>,
+    /// Three per triangle.
+    pub indices: Vec<u32>,
+}
+
+/// Vertex of a [`RibbonMesh`].
+#[derive(Clone, Copy, Debug)]
+pub struct RibbonVertex {
+    pub pos: Pos2,
+    /// Segment opacity here.
+    pub opacity: f32,
+    /// Share of the paint laid down: zero on the feathered rim, below one
+    /// where the ribbon is thinner than the feather.
+    pub strength: f32,
+}
+
+/// Cross-section of a ribbon at one of its points.
+#[derive(Clone, Copy)]
+struct Section {
+    center: Pos2,
+    /// Half-width, at least half the feather.
+    radius: f32,
+    opacity: f32,
+    strength: f32,
+}
+
+impl RibbonMesh {
+    fn vertex(&mut self, pos: Pos2, section: Section, solid: bool) -> u32 {
+        self.vertices.push(RibbonVertex {
+            pos,
+            opacity: section.opacity,
+            strength: if solid { section.strength } else { 0.0 },
+        });
+        self.vertices.len() as u32 - 1
+    }
+
+    /// One segment's strip: a solid core between feathered edges. Each end
+    /// is a section with its left and right edge offsets per half-width.
+    fn band(&mut self, ends: [(Section, Vec2, Vec2); 2], feather: f32) {
+        let base = self.vertices.len() as u32;
+        for (section, left, right) in ends {
+            let (center, radius) = (section.center, section.radius);
+            let inner = (radius - feather * 0.5).max(0.0);
+            let outer = radius + feather * 0.5;
+            self.vertex(center + left * outer, section, false);
+            self.vertex(center + left * inner, section, true);
+            self.vertex(center - right * inner, section, true);
+            self.vertex(center - right * outer, section, false);
+        }
+        for j in 0..3 {
+            let (a, b) = (base + j, base + j + 1);
+            self.indices.extend([a, b, a + 4, b, b + 4, a + 4]);
+        }
+    }
+
+    /// Disc sector around the section's center between two directions,
+    /// turning by `sweep` radians, with a feathered rim. Its triangles meet
+    /// at `hub`, an offset per half-width from the center. The end
+    /// directions are used as given, so the sector shares its sides exactly
+    /// with the bands next to it.
+    fn fan(
+        &mut self,
+        section: Section,
+        hub: Vec2,
+        [from, to]: [Vec2; 2],
+        sweep: f32,
+        feather: f32,
+    ) {
+        let steps = (sweep.abs() / ARC_STEP).ceil().max(1.0) as u32;
+        let (center, radius) = (section.center, section.radius);
+        let inner = (radius - feather * 0.5).max(0.0);
+        let outer = radius + feather * 0.5;
+        let start = from.angle();
+
+        let base = self.vertex(center + hub * inner, section, true);
+        for s in 0..=steps {
+            let dir = match s {
+                0 => from,
+                s if s == steps => to,
+                s => Vec2::angled(start + sweep * s as f32 / steps as f32),
+            };
+            self.vertex(center + dir * inner, section, true);
+            self.vertex(center + dir * outer, section, false);
+        }
+        for s in 0..steps {
+            let (i, o) = (base + 1 + s * 2, base + 2 + s * 2);
+            self.indices.extend([base, i, i + 2, i, o, o + 2, i, o + 2, i + 2]);
+        }
+    }
+}
+
+/// Left-hand unit normal of each segment of a polyline.
+pub fn normals(points: &[Pos2]) -> Vec<Vec2> {
+    let mut prev = Vec2::Y;
+    points
+        .windows(2)
+        .map(|w| {
+            let d = w[1] - w[0];
+            if d.length_sq() > f32::EPSILON {
+                prev = Vec2::new(-d.y, d.x).normalized();
+            }
+            prev
+        })
+        .collect()
+}
+
+/// How the edges of a ribbon meet at a vertex between two segments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Join {
+    /// Both edges meet at this offset per half-width, see [`miter`].
+    Miter(Vec2),
+    /// The outside of the turn is rounded, turning by `turn` radians
+    /// (negative when the outside is on the left). The inside edges meet
+    /// at `inner` per half-width.
+    Round { inner: Vec2, turn: f32 },
+}
+
+/// Join between segments with normals `n1` and `n2`. Every renderer builds
+/// its joins from this, so live strokes and exports agree.
+///
+/// At round joins the inside edges meet where they cross, unless that is
+/// further along the segments than `reach` half-widths; then the point is
+/// pulled in to it, so short segments don't fold over.
+pub fn join(n1: Vec2, n2: Vec2, reach: f32) -> Join {
+    if let Some(m) = miter(n1, n2) {
+        return Join::Miter(m);
+    }
+    let turn = cross(n1, n2).atan2(n1.dot(n2));
+    let sum = n1 + n2;
+    let inner = if sum.length_sq() > 1e-6 {
+        // Half the turn is the angle between the bisector and each normal
+        let m = sum.normalized();
+        let (cos, sin) = (m.dot(n1), cross(m, n1).abs());
+        m * (1.0 / cos).min(reach / sin)
+    } else {
+        Vec2::ZERO
+    };
+    Join::Round {
+        inner: if turn < 0.0 { -inner } else { inner },
+        turn,
+    }
+}
+
+/// Left and right edge offsets, per half-width, of a band with `normal`
+/// where it meets `join` (or a cap, without one). At round joins the outside
+/// edge runs on to the join's sector and the inside ends on its inner point.
+fn offsets(join: Option<Join>, normal: Vec2) -> (Vec2, Vec2) {
+    match join {
+        None => (normal, normal),
+        Some(Join::Miter(m)) => (m, m),
+        Some(Join::Round { inner, turn }) if turn < 0.0 => (normal, -inner),
+        Some(Join::Round { inner, .. }) => (inner, normal),
+    }
+}
+
+/// Offset direction at a vertex between segments with normals `n1` and `n2`,
+/// scaled so an offset of one half-width lands on both edges; `None` where
+/// that miter would exceed `MITER_LIMIT`.
+pub fn miter(n1: Vec2, n2: Vec2) -> Option<Vec2> {
+    let sum = n1 + n2;
+    if sum.length_sq() <= 1e-6 {
+        return None;
+    }
+    let m = sum.normalized();
+    let cos = m.dot(n1);
+    (cos >= 1.0 / MITER_LIMIT).then(|| m / cos)
+}
+
+/// Z component of the cross product; positive when `b` turns towards the
+/// left-hand normal of `a`.
+pub fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
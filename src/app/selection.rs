@@ -0,0 +1,443 @@
+// app/selection.rs
+//! Selecting strokes and blots on a layer and editing them as a group:
+//! moving, scaling and rotating through handles on the selection's bounding
+//! box, deleting and duplicating.
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use crate::app::history::remap_pruned;
+use crate::app::layers::Layer;
+use crate::app::utils::math::{point_in_polygon, Affine};
+
+/// Side of the square transform handles, in screen points.
+pub const HANDLE_SIZE: f32 = 8.0;
+
+/// Distance of the rotate handle above the bounding box, in screen points.
+pub const ROTATE_OFFSET: f32 = 24.0;
+
+/// How far duplicates land from the originals, in canvas points.
+pub const DUPLICATE_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
+
+/// Smallest distance from the pivot a scale handle can be dragged to.
+const MIN_SCALE_EXTENT: f32 = 1.0;
+
+/// Area swept by a selection gesture.
+#[derive(Clone, Debug)]
+pub enum Marquee {
+    Rect(Rect),
+    Lasso(Vec<Pos2>),
+}
+
+impl Marquee {
+    pub fn contains(&self, p: Pos2) -> bool {
+        match self {
+            Marquee::Rect(rect) => rect.contains(p),
+            Marquee::Lasso(points) => point_in_polygon(p, points),
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Marquee::Rect(rect) => *rect,
+            Marquee::Lasso(points) => Rect::from_points(points),
+        }
+    }
+}
+
+/// Strokes and blots picked on one layer, by index.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    /// Layer id.
+    pub layer: u32,
+    /// Ascending indices into the layer's `strokes`.
+    pub strokes: Vec<usize>,
+    /// Ascending indices into the layer's `blots`.
+    pub blots: Vec<usize>,
+}
+
+impl Selection {
+    /// Strokes with any segment end inside `marquee`, and blots centered in it.
+    pub fn in_marquee(layer: &Layer, marquee: &Marquee) -> Self {
+        let area = marquee.bounds();
+        let strokes = layer
+            .strokes
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                s.segments.iter().any(|seg| {
+                    [seg.start, seg.end]
+                        .into_iter()
+                        .any(|p| area.contains(p) && marquee.contains(p))
+                })
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let blots = layer
+            .blots
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| area.contains(b.pos) && marquee.contains(b.pos))
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            layer: layer.id,
+            strokes,
+            blots,
+        }
+    }
+
+    /// The stroke or blot closest to `pos`, within `tolerance`. Uses the
+    /// layer's index, which must be in sync.
+    pub fn at_point(layer: &Layer, pos: Pos2, tolerance: f32) -> Self {
+        let segment = layer.index.nearest_segment(pos, tolerance);
+        let blot = layer.index.nearest_blot(pos, tolerance, &layer.blots);
+        let (strokes, blots) = match (segment, blot) {
+            (Some((s, sd)), Some((_, bd))) if sd <= bd => (vec![s.stroke], Vec::new()),
+            (_, Some((b, _))) => (Vec::new(), vec![b]),
+            (Some((s, _)), None) => (vec![s.stroke], Vec::new()),
+            (None, None) => (Vec::new(), Vec::new()),
+        };
+        Self {
+            layer: layer.id,
+            strokes,
+            blots,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty() && self.blots.is_empty()
+    }
+
+    /// World-space box around the selected segments and blots.
+    pub fn bounds(&self, layer: &Layer) -> Option<Rect> {
+        let mut bounds = Rect::NOTHING;
+        for stroke in self.strokes.iter().filter_map(|&i| layer.strokes.get(i)) {
+            for seg in &stroke.segments {
+                bounds.extend_with(seg.start);
+                bounds.extend_with(seg.end);
+            }
+        }
+        for b in self.blots.iter().filter_map(|&i| layer.blots.get(i)) {
+            bounds = bounds.union(Rect::from_center_size(b.pos, Vec2::splat(b.radius * 2.0)));
+        }
+        bounds.is_finite().then_some(bounds)
+    }
+
+    /// Apply `transform` to the selected elements. Widths and radii follow
+    /// its average scale.
+    pub fn transform(&self, layer: &mut Layer, transform: &Affine) {
+        apply_transform(layer, &self.strokes, &self.blots, transform);
+    }
+
+    /// Remove the selected elements from `layer`.
+    pub fn remove_from(&self, layer: &mut Layer) {
+        let mut i = 0;
+        layer.strokes.retain(|_| {
+            i += 1;
+            self.strokes.binary_search(&(i - 1)).is_err()
+        });
+        let mut i = 0;
+        layer.blots.retain(|_| {
+            i += 1;
+            self.blots.binary_search(&(i - 1)).is_err()
+        });
+        layer.index.invalidate();
+    }
+
+    /// Append offset copies of the selected elements to `layer`; returns the
+    /// selection of the copies.
+    pub fn duplicate_in(&self, layer: &mut Layer, offset: Vec2) -> Selection {
+        let shift = Affine::translate(offset);
+        let copies: Vec<_> = self
+            .strokes
+            .iter()
+            .filter_map(|&i| layer.strokes.get(i).cloned())
+            .collect();
+        let blots: Vec<_> = self
+            .blots
+            .iter()
+            .filter_map(|&i| layer.blots.get(i).cloned())
+            .collect();
+
+        let copy = Selection {
+            layer: layer.id,
+            strokes: (layer.strokes.len()..layer.strokes.len() + copies.len()).collect(),
+            blots: (layer.blots.len()..layer.blots.len() + blots.len()).collect(),
+        };
+        layer.strokes.extend(copies);
+        layer.blots.extend(blots);
+        copy.transform(layer, &shift);
+        copy
+    }
+
+    /// Strokes at the (ascending) `removed` indices were dropped from the layer.
+    pub fn strokes_pruned(&mut self, removed: &[usize]) {
+        remap_pruned(&mut self.strokes, removed);
+    }
+}
+
+/// Apply `transform` to the strokes and blots at the given indices of `layer`.
+pub fn apply_transform(layer: &mut Layer, strokes: &[usize], blots: &[usize], transform: &Affine) {
+    let scale = transform.mean_scale();
+    for &i in strokes {
+        let Some(stroke) = layer.strokes.get_mut(i) else {
+            continue;
+        };
+        for seg in &mut stroke.segments {
+            seg.start = transform.apply(seg.start);
+            seg.end = transform.apply(seg.end);
+            let dir = transform.apply_vec(seg.dir);
+            if dir.length_sq() > f32::EPSILON {
+                seg.dir = dir.normalized();
+            }
+        }
+        if let Some(thickness) = &mut stroke.thickness {
+            *thickness *= scale;
+        }
+    }
+    for &i in blots {
+        let Some(b) = layer.blots.get_mut(i) else {
+            continue;
+        };
+        b.pos = transform.apply(b.pos);
+        b.radius *= scale;
+    }
+    layer.index.invalidate();
+}
+
+/// Part of the selection box a drag starts on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Handle {
+    /// Inside the box.
+    Move,
+    /// A corner, see [`corners`].
+    Scale(usize),
+    /// The knob above the box.
+    Rotate,
+}
+
+/// Box corners, clockwise from the top-left.
+pub fn corners(bounds: Rect) -> [Pos2; 4] {
+    [
+        bounds.left_top(),
+        bounds.right_top(),
+        bounds.right_bottom(),
+        bounds.left_bottom(),
+    ]
+}
+
+/// World position of the rotate knob at camera `zoom`.
+pub fn rotate_knob(bounds: Rect, zoom: f32) -> Pos2 {
+    bounds.center_top() - Vec2::new(0.0, ROTATE_OFFSET / zoom)
+}
+
+/// Handle of the box `bounds` under world position `pos` at camera `zoom`.
+pub fn handle_at(bounds: Rect, pos: Pos2, zoom: f32) -> Option<Handle> {
+    let reach = HANDLE_SIZE / zoom;
+    if rotate_knob(bounds, zoom).distance(pos) <= reach {
+        return Some(Handle::Rotate);
+    }
+    if let Some(corner) = corners(bounds).iter().position(|c| c.distance(pos) <= reach) {
+        return Some(Handle::Scale(corner));
+    }
+    bounds.expand(reach * 0.5).contains(pos).then_some(Handle::Move)
+}
+
+/// A transform in progress, fed one pointer position at a time.
+#[derive(Clone, Debug)]
+pub struct TransformDrag {
+    pub handle: Handle,
+    /// Pointer position the last step ended at.
+    last: Pos2,
+    /// Fixed point of scaling (the opposite corner) and rotation (the center).
+    pivot: Pos2,
+    /// Everything applied since the drag started.
+    pub total: Affine,
+}
+
+impl TransformDrag {
+    pub fn new(handle: Handle, bounds: Rect, pos: Pos2) -> Self {
+        let pivot = match handle {
+            Handle::Scale(corner) => corners(bounds)[(corner + 2) % 4],
+            Handle::Move | Handle::Rotate => bounds.center(),
+        };
+        Self {
+            handle,
+            last: pos,
+            pivot,
+            total: Affine::IDENTITY,
+        }
+    }
+
+    /// Transform taking the elements from the last position to `pos`.
+    pub fn step(&mut self, pos: Pos2) -> Affine {
+        let prev = self.last - self.pivot;
+        let cur = pos - self.pivot;
+        let step = match self.handle {
+            Handle::Move => {
+                self.last = pos;
+                Affine::translate(cur - prev)
+            }
+            Handle::Rotate => {
+                self.last = pos;
+                Affine::rotate_about(self.pivot, cur.angle() - prev.angle())
+            }
+            Handle::Scale(_) => {
+                // Per axis; an axis can't be squashed through the pivot
+                let factor = |prev: f32, cur: f32| {
+                    if prev.abs() < MIN_SCALE_EXTENT
+                        || cur.abs() < MIN_SCALE_EXTENT
+                        || prev * cur < 0.0
+                    {
+                        1.0
+                    } else {
+                        cur / prev
+                    }
+                };
+                let factor = Vec2::new(factor(prev.x, cur.x), factor(prev.y, cur.y));
+                self.last = self.pivot + prev * factor;
+                Affine::scale_about(self.pivot, factor)
+            }
+        };
+        self.total = self.total.then(&step);
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Color32;
+
+    use crate::app::blend::BlendMode;
+    use crate::app::brushes::blotter::Blot;
+    use crate::app::brushes::crystal::StrokeData;
+    use crate::app::brushes::BrushKind;
+    use crate::app::state::AppState;
+
+    fn close(a: Pos2, b: Pos2) -> bool {
+        a.distance(b) < 1e-3
+    }
+
+    /// One-segment stroke from `a` to `b`.
+    fn stroke(a: Pos2, b: Pos2) -> StrokeData {
+        let mut stroke = StrokeData::new(Color32::WHITE, false);
+        stroke.add_segment(a, b, (b - a).normalized(), [1.0; 2], 0.0);
+        stroke
+    }
+
+    fn blot(pos: Pos2) -> Blot {
+        Blot {
+            pos,
+            radius: 2.0,
+            color: Color32::WHITE,
+            softness: 0.0,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    /// Strokes along y = 0, 10, 20 and blots at x = 0, 10, 20.
+    fn layer() -> Layer {
+        let mut layer = Layer::new(0, "Layer 1");
+        for i in 0..3 {
+            let y = i as f32 * 10.0;
+            layer.strokes.push(stroke(Pos2::new(0.0, y), Pos2::new(10.0, y)));
+            layer.blots.push(blot(Pos2::new(y, 40.0)));
+        }
+        layer
+    }
+
+    fn selection(strokes: Vec<usize>, blots: Vec<usize>) -> Selection {
+        Selection {
+            layer: 0,
+            strokes,
+            blots,
+        }
+    }
+
+    #[test]
+    fn scaling_stops_short_of_the_pivot() {
+        let bounds = Rect::from_min_max(Pos2::ZERO, Pos2::new(10.0, 10.0));
+        // Bottom-right corner, scaling about the top-left
+        let mut drag = TransformDrag::new(Handle::Scale(2), bounds, bounds.max);
+
+        assert_eq!(drag.step(Pos2::new(-5.0, -5.0)), Affine::IDENTITY);
+        assert_eq!(drag.step(Pos2::new(0.5, 0.5)), Affine::IDENTITY);
+        drag.step(Pos2::new(5.0, 20.0));
+        assert!(close(drag.total.apply(bounds.max), Pos2::new(5.0, 20.0)));
+        assert!(close(drag.total.apply(Pos2::ZERO), Pos2::ZERO));
+
+        // One axis through the pivot: only the other one scales
+        drag.step(Pos2::new(-5.0, 10.0));
+        assert!(close(drag.total.apply(bounds.max), Pos2::new(5.0, 10.0)));
+    }
+
+    #[test]
+    fn rotating_then_undoing_restores_the_elements() {
+        let mut state = AppState::without_user_config();
+        state.layers[0] = layer();
+        let positions = |state: &AppState| {
+            let layer = state.active_layer();
+            let ends = layer.strokes.iter().flat_map(|s| &s.segments).flat_map(|s| [s.start, s.end]);
+            ends.chain(layer.blots.iter().map(|b| b.pos)).collect::<Vec<_>>()
+        };
+        let before = positions(&state);
+
+        let selected = selection(vec![0, 2], vec![1]);
+        let bounds = selected.bounds(state.active_layer()).unwrap();
+        let knob = rotate_knob(bounds, state.camera.zoom);
+        state.selection = Some(selected);
+        state.active_brush = BrushKind::SELECT;
+        state.pointer_down(knob);
+        // A quarter turn about the center
+        state.pointer_drag(bounds.center() + (knob - bounds.center()).rot90());
+        state.pointer_up();
+
+        let rotated = positions(&state);
+        assert!(!close(rotated[0], before[0]));
+        assert!(close(rotated[2], before[2]), "unselected strokes stay put");
+        assert!(!close(rotated[7], before[7]) && close(rotated[6], before[6]));
+
+        state.undo();
+        let undone = positions(&state);
+        assert!(undone.iter().zip(&before).all(|(&a, &b)| close(a, b)), "{undone:?}");
+    }
+
+    #[test]
+    fn duplicates_are_offset_and_selected() {
+        let mut layer = layer();
+        let copy = selection(vec![1], vec![0, 2]).duplicate_in(&mut layer, DUPLICATE_OFFSET);
+
+        assert_eq!(copy.strokes, [3]);
+        assert_eq!(copy.blots, [3, 4]);
+        let seg = &layer.strokes[3].segments[0];
+        assert!(close(seg.start, Pos2::new(0.0, 10.0) + DUPLICATE_OFFSET));
+        assert!(close(layer.blots[4].pos, layer.blots[2].pos + DUPLICATE_OFFSET));
+        assert!(close(layer.strokes[1].segments[0].start, Pos2::new(0.0, 10.0)));
+    }
+
+    #[test]
+    fn removing_keeps_the_other_elements_in_order() {
+        let mut layer = layer();
+        selection(vec![0, 2], vec![1]).remove_from(&mut layer);
+
+        assert_eq!(layer.strokes.len(), 1);
+        assert!(close(layer.strokes[0].segments[0].start, Pos2::new(0.0, 10.0)));
+        let xs: Vec<f32> = layer.blots.iter().map(|b| b.pos.x).collect();
+        assert_eq!(xs, [0.0, 20.0]);
+    }
+
+    #[test]
+    fn transforms_scale_widths_and_radii() {
+        let mut layer = layer();
+        layer.strokes[0].thickness = Some(2.0);
+        let double = Affine::scale_about(Pos2::ZERO, Vec2::splat(2.0));
+        apply_transform(&mut layer, &[0], &[0], &double);
+
+        assert_eq!(layer.strokes[0].thickness, Some(4.0));
+        assert!(close(layer.strokes[0].segments[0].end, Pos2::new(20.0, 0.0)));
+        assert_eq!(layer.blots[0].radius, 4.0);
+        assert_eq!(layer.blots[1].radius, 2.0);
+    }
+}
@@ -0,0 +1,332 @@
+// app/spatial.rs
+//! Uniform-grid spatial index over canvas segments and blots.
+//!
+//! Growth collision, hit-testing and viewport culling query this instead of
+//! walking every element each frame.
+
+use std::collections::HashMap;
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use crate::app::brushes::blotter::Blot;
+use crate::app::brushes::crystal::StrokeData;
+use crate::app::utils::math::distance_to_segment;
+
+/// Edge length of one grid cell, in canvas points.
+const CELL_SIZE: f32 = 32.0;
+
+/// Bucketed grid mapping cells to item ids.
+pub struct SpatialGrid {
+    cell: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell: f32) -> Self {
+        Self {
+            cell: cell.max(1.0),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    fn cell_range(&self, rect: Rect) -> (i32, i32, i32, i32) {
+        (
+            (rect.min.x / self.cell).floor() as i32,
+            (rect.min.y / self.cell).floor() as i32,
+            (rect.max.x / self.cell).floor() as i32,
+            (rect.max.y / self.cell).floor() as i32,
+        )
+    }
+
+    /// Register `id` in every cell overlapped by `bounds`.
+    pub fn insert(&mut self, bounds: Rect, id: usize) {
+        let (x0, y0, x1, y1) = self.cell_range(bounds);
+        for cy in y0..=y1 {
+            for cx in x0..=x1 {
+                self.cells.entry((cx, cy)).or_default().push(id);
+            }
+        }
+    }
+
+    /// Register `id`, already inserted with bounds `old`, in the cells `new`
+    /// overlaps beyond those. Cells only `old` overlaps keep the id; queries
+    /// filter by the item's current bounds anyway.
+    pub fn grow(&mut self, old: Rect, new: Rect, id: usize) {
+        let (ox0, oy0, ox1, oy1) = self.cell_range(old);
+        let (x0, y0, x1, y1) = self.cell_range(new);
+        for cy in y0..=y1 {
+            for cx in x0..=x1 {
+                if !((ox0..=ox1).contains(&cx) && (oy0..=oy1).contains(&cy)) {
+                    self.cells.entry((cx, cy)).or_default().push(id);
+                }
+            }
+        }
+    }
+
+    /// Ids whose bounds may overlap `rect`, sorted and deduplicated.
+    pub fn query(&self, rect: Rect) -> Vec<usize> {
+        let mut out = Vec::new();
+        if !rect.is_finite() {
+            return out;
+        }
+
+        let (x0, y0, x1, y1) = self.cell_range(rect);
+
+        // Huge query rects: scanning buckets is cheaper than walking empty cells.
+        let span = (x1 - x0 + 1) as i64 * (y1 - y0 + 1) as i64;
+        if span > self.cells.len() as i64 {
+            for (&(cx, cy), ids) in &self.cells {
+                if (x0..=x1).contains(&cx) && (y0..=y1).contains(&cy) {
+                    out.extend_from_slice(ids);
+                }
+            }
+        } else {
+            for cy in y0..=y1 {
+                for cx in x0..=x1 {
+                    if let Some(ids) = self.cells.get(&(cx, cy)) {
+                        out.extend_from_slice(ids);
+                    }
+                }
+            }
+        }
+
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+}
+
+/// Segment `segment` of the stroke at `stroke` in a layer's `strokes`, with
+/// its geometry at index time.
+#[derive(Clone, Copy, Debug)]
+pub struct SegmentRef {
+    pub stroke: usize,
+    pub segment: usize,
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+/// Spatial index kept alongside a `Layer`'s `strokes` / `blots`.
+///
+/// Growth is picked up incrementally by [`SceneIndex::sync`]: strokes and
+/// blots appended at the end, segments appended to a stroke, and growing
+/// segments extending their end. Anything else that reorders, removes or
+/// moves elements must call [`SceneIndex::invalidate`] (or
+/// [`SceneIndex::invalidate_segments`] when only strokes changed).
+pub struct SceneIndex {
+    segments: Vec<SegmentRef>,
+    segment_grid: SpatialGrid,
+    segments_dirty: bool,
+    /// Number of segments indexed for each stroke.
+    indexed_segments: Vec<usize>,
+    /// Ids of segments that were still growing when last indexed.
+    growing: Vec<usize>,
+
+    blot_grid: SpatialGrid,
+    indexed_blots: usize,
+    blots_dirty: bool,
+}
+
+impl SceneIndex {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            segment_grid: SpatialGrid::new(CELL_SIZE),
+            segments_dirty: true,
+            indexed_segments: Vec::new(),
+            growing: Vec::new(),
+            blot_grid: SpatialGrid::new(CELL_SIZE),
+            indexed_blots: 0,
+            blots_dirty: true,
+        }
+    }
+
+    /// Force a full rebuild on the next sync.
+    pub fn invalidate(&mut self) {
+        self.segments_dirty = true;
+        self.blots_dirty = true;
+    }
+
+    /// Rebuild segments on the next sync (strokes reordered, moved or removed).
+    pub fn invalidate_segments(&mut self) {
+        self.segments_dirty = true;
+    }
+
+    /// Bring the index up to date with the canvas.
+    pub fn sync(&mut self, strokes: &[StrokeData], blots: &[Blot]) {
+        let shrunk = strokes.len() < self.indexed_segments.len()
+            || strokes
+                .iter()
+                .zip(&self.indexed_segments)
+                .any(|(s, &n)| s.segments.len() < n);
+        if self.segments_dirty || shrunk {
+            self.rebuild_segments(strokes);
+        } else {
+            self.update_segments(strokes);
+        }
+
+        if self.blots_dirty || blots.len() < self.indexed_blots {
+            self.blot_grid.clear();
+            self.indexed_blots = 0;
+            self.blots_dirty = false;
+        }
+
+        for (i, b) in blots.iter().enumerate().skip(self.indexed_blots) {
+            self.blot_grid.insert(blot_bounds(b), i);
+        }
+        self.indexed_blots = blots.len();
+    }
+
+    fn rebuild_segments(&mut self, strokes: &[StrokeData]) {
+        self.segments.clear();
+        self.segment_grid.clear();
+        self.indexed_segments.clear();
+        self.growing.clear();
+        self.segments_dirty = false;
+        self.update_segments(strokes);
+    }
+
+    /// Index segments appended since the last sync and extend the ones that
+    /// were growing then.
+    fn update_segments(&mut self, strokes: &[StrokeData]) {
+        for id in std::mem::take(&mut self.growing) {
+            let indexed = self.segments[id];
+            let Some(seg) = strokes
+                .get(indexed.stroke)
+                .and_then(|s| s.segments.get(indexed.segment))
+            else {
+                continue;
+            };
+            if seg.start != indexed.start || seg.end != indexed.end {
+                self.segment_grid.grow(
+                    Rect::from_two_pos(indexed.start, indexed.end),
+                    Rect::from_two_pos(seg.start, seg.end),
+                    id,
+                );
+                self.segments[id].start = seg.start;
+                self.segments[id].end = seg.end;
+            }
+            if seg.growing {
+                self.growing.push(id);
+            }
+        }
+
+        self.indexed_segments.resize(strokes.len(), 0);
+        for (si, stroke) in strokes.iter().enumerate() {
+            let known = self.indexed_segments[si];
+            for (j, seg) in stroke.segments.iter().enumerate().skip(known) {
+                let id = self.segments.len();
+                self.segments.push(SegmentRef {
+                    stroke: si,
+                    segment: j,
+                    start: seg.start,
+                    end: seg.end,
+                });
+                self.segment_grid.insert(Rect::from_two_pos(seg.start, seg.end), id);
+                if seg.growing {
+                    self.growing.push(id);
+                }
+            }
+            self.indexed_segments[si] = stroke.segments.len();
+        }
+    }
+
+    /// Segments whose bounds overlap `rect`, in stroke/segment order.
+    pub fn segments_in(&self, rect: Rect) -> Vec<SegmentRef> {
+        let mut found: Vec<SegmentRef> = self
+            .segment_grid
+            .query(rect)
+            .into_iter()
+            .map(|id| self.segments[id])
+            .filter(|s| Rect::from_two_pos(s.start, s.end).intersects(rect))
+            .collect();
+        found.sort_unstable_by_key(|s| (s.stroke, s.segment));
+        found
+    }
+
+    /// Indices of blots whose bounds overlap `rect`, in ascending (paint) order.
+    pub fn blots_in(&self, rect: Rect, blots: &[Blot]) -> Vec<usize> {
+        self.blot_grid
+            .query(rect)
+            .into_iter()
+            .filter(|&i| i < blots.len() && blot_bounds(&blots[i]).intersects(rect))
+            .collect()
+    }
+
+    /// Closest segment within `max_dist` of `pos`.
+    pub fn nearest_segment(&self, pos: Pos2, max_dist: f32) -> Option<(SegmentRef, f32)> {
+        let area = Rect::from_center_size(pos, Vec2::splat(max_dist * 2.0));
+        self.segments_in(area)
+            .into_iter()
+            .map(|s| (s, distance_to_segment(pos, s.start, s.end)))
+            .filter(|(_, d)| *d <= max_dist)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Closest blot (by distance to its edge) within `max_dist` of `pos`.
+    pub fn nearest_blot(&self, pos: Pos2, max_dist: f32, blots: &[Blot]) -> Option<(usize, f32)> {
+        let area = Rect::from_center_size(pos, Vec2::splat(max_dist * 2.0));
+        self.blots_in(area, blots)
+            .into_iter()
+            .map(|i| (i, (blots[i].pos.distance(pos) - blots[i].radius).max(0.0)))
+            .filter(|(_, d)| *d <= max_dist)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Bounding box of a blot including its feathered halo.
+fn blot_bounds(b: &Blot) -> Rect {
+    let r = b.radius * (1.0 + b.softness.max(0.0) * 0.3);
+    Rect::from_center_size(b.pos, Vec2::splat(r * 2.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+
+    use super::*;
+    use crate::app::brushes::crystal::CrystalBrush;
+    use crate::app::simulation::rng_from;
+
+    fn indexed(index: &SceneIndex) -> Vec<(usize, usize, Pos2, Pos2)> {
+        let everything = Rect::from_center_size(Pos2::ZERO, Vec2::splat(1e6));
+        index
+            .segments_in(everything)
+            .into_iter()
+            .map(|s| (s.stroke, s.segment, s.start, s.end))
+            .collect()
+    }
+
+    #[test]
+    fn growth_is_indexed_incrementally() {
+        let mut brush = CrystalBrush::new();
+        let mut strokes = Vec::new();
+        for (i, y) in [0.0, 40.0].into_iter().enumerate() {
+            let mut stroke = StrokeData::new(Color32::WHITE, true);
+            let (a, b) = (Pos2::new(0.0, y), Pos2::new(20.0, y + 5.0 * i as f32));
+            stroke.add_segment(a, b, (b - a).normalized(), [1.0; 2], 0.0);
+            strokes.push(stroke);
+        }
+
+        let mut index = SceneIndex::new();
+        index.sync(&strokes, &[]);
+        for step in 0..400 {
+            brush.growth_step(&mut strokes, 2.0, None, 0.0, &mut rng_from(step));
+            index.sync(&strokes, &[]);
+            let mut fresh = SceneIndex::new();
+            fresh.sync(&strokes, &[]);
+            assert_eq!(indexed(&index), indexed(&fresh), "step {step}");
+
+            let probe = Rect::from_center_size(Pos2::new(30.0, 20.0), Vec2::splat(24.0));
+            let near = |index: &SceneIndex| -> Vec<(usize, usize)> {
+                index.segments_in(probe).iter().map(|s| (s.stroke, s.segment)).collect()
+            };
+            assert_eq!(near(&index), near(&fresh), "step {step}");
+        }
+        assert!(strokes.iter().map(|s| s.segments.len()).sum::<usize>() > 2);
+    }
+}
@@ -1,15 +1,34 @@
 // app/state.rs
 //! Central application state for the modular Crystal Painter.
 
-use eframe::egui::{self, Color32, Pos2};
+use eframe::egui::{self, Color32, Pos2, Rect};
+use crate::app::camera::Camera;
+use crate::app::clipboard::Fragment;
 use crate::app::painter::CanvasPainter;
-use crate::app::brushes::{BrushKind, crystal, drip, blotter};
-use crate::app::brushes::blotter_props::BlotterProps;
+use crate::app::history::{History, Operation};
+use crate::app::layers::{Layer, LayerFlag};
+use crate::app::simulation::{Simulation, TIMESTEP};
+use crate::app::document::Document;
+use crate::app::presets::{PresetError, PresetLibrary};
+use crate::app::replay::{Controls, InputEvent, InputRecorder, Player, Recording};
+use crate::app::export::timelapse::{Recorder, TimelapseSettings};
+use crate::app::export::{raster, svg, ExportScene};
+use crate::app::brushes::{
+    BrushContext, BrushKind, CanvasElement, CanvasMut, LayerEdit, TickContext,
+};
+use crate::app::brushes::registry::BrushRegistry;
+use crate::app::selection::{self, Selection, DUPLICATE_OFFSET};
 use crate::app::ui;
 
+use std::path::PathBuf;
 use std::time::Instant;
 
-use crate::app::brushes::blotter::Blot;
+/// Stroke width used when a stroke has no explicit thickness.
+pub const STROKE_BASE_SIZE: f32 = 2.0;
+
+/// Simulation steps a fast replay runs per frame, so the window stays
+/// responsive while it plays.
+const FAST_REPLAY_STEPS: u32 = 240;
 
 pub struct AppState {
     // UI
@@ -21,21 +40,59 @@ pub struct AppState {
     // brush selection
     pub active_brush: BrushKind,
 
-    // brushes
-    pub crystal: crystal::CrystalBrush,
-    pub drip: drip::DripBrush,
+    // brushes, keyed by kind
+    pub brushes: BrushRegistry,
+
+    // named brush presets and the name being edited in the preset picker
+    pub presets: PresetLibrary,
+    pub preset_name: String,
+    /// Why the user library failed to load; it is then never written back,
+    /// so the file on disk isn't replaced by an empty library.
+    pub presets_error: Option<PresetError>,
+
+    // canvas layers, bottom to top; never empty
+    pub layers: Vec<Layer>,
+    pub active_layer: usize,
+    pub next_layer_id: u32,
+
+    // undo / redo log
+    pub history: History,
+
+    // document
+    pub document_path: Option<PathBuf>,
+    pub status: Option<String>,
+
+    /// Fixed-step clock and seed; saved with the document so growth replays.
+    pub sim: Simulation,
+    pub last_frame: Instant,
 
-    // new blotter engine + props
-    pub blotter: blotter::Blotter,
-    pub blotter_props: BlotterProps,
+    // canvas area in the last frame (export viewport)
+    pub canvas_rect: Rect,
 
-    // canvas stored elements
-    pub strokes: Vec<crystal::StrokeData>,
-    pub blots: Vec<Blot>,
+    // world <-> screen view; `panning` is set while a drag moves the view
+    pub camera: Camera,
+    pub panning: bool,
 
-    // pointer path tracking
-    pub current_points: Vec<Pos2>,
-    pub last_tick: Instant,
+    // export output size multiplier
+    pub export_scale: f32,
+
+    // time-lapse options and the recording in progress, if any
+    pub timelapse: TimelapseSettings,
+    pub recorder: Option<Recorder>,
+
+    // input recording / playback and the playback options
+    pub input_recorder: Option<InputRecorder>,
+    pub player: Option<Player>,
+    pub replay_fast: bool,
+    pub replay_current_props: bool,
+
+    // gesture tracking; `gesture_layer` is the layer id being painted on
+    pub gesture_layer: Option<u32>,
+    pub gesture_blot_start: usize,
+    pub pen_pressure: Option<f32>,
+
+    // selected elements
+    pub selection: Option<Selection>,
 
     // simulation controls
     pub paused: bool,
@@ -50,6 +107,23 @@ pub struct AppState {
 
 impl Default for AppState {
     fn default() -> Self {
+        let mut state = Self::without_user_config();
+        match PresetLibrary::load_user() {
+            Ok(presets) => state.presets = presets,
+            Err(e) => {
+                state.status = Some(format!("Presets not loaded: {e}"));
+                // Without a config dir there is no file to protect
+                state.presets_error = Some(e).filter(|e| !matches!(e, PresetError::NoConfigDir));
+            }
+        }
+        state
+    }
+}
+
+impl AppState {
+    /// Fresh state that doesn't read the user's preset library, for headless
+    /// rendering and tests.
+    pub fn without_user_config() -> Self {
         Self {
             current_color: Color32::from_rgb(255, 255, 255),
             canvas_bg: Color32::from_rgb(59, 47, 47),
@@ -60,19 +134,42 @@ impl Default for AppState {
             ],
             selected_swatch: None,
 
-            active_brush: BrushKind::Crystal,
+            active_brush: BrushKind::CRYSTAL,
+
+            brushes: BrushRegistry::with_defaults(),
 
-            crystal: crystal::CrystalBrush::new(),
-            drip: drip::DripBrush::new(),
+            presets: PresetLibrary::default(),
+            preset_name: String::new(),
+            presets_error: None,
 
-            blotter: blotter::Blotter::new(),
-            blotter_props: BlotterProps::default(),
+            layers: vec![Layer::new(0, "Layer 1")],
+            active_layer: 0,
+            next_layer_id: 1,
 
-            strokes: Vec::new(),
-            blots: Vec::new(),
+            history: History::new(),
 
-            current_points: Vec::new(),
-            last_tick: Instant::now(),
+            document_path: None,
+            status: None,
+
+            sim: Simulation::new(rand::random()),
+            last_frame: Instant::now(),
+
+            canvas_rect: Rect::from_min_size(Pos2::ZERO, egui::vec2(800.0, 600.0)),
+            camera: Camera::default(),
+            panning: false,
+            export_scale: 1.0,
+            timelapse: TimelapseSettings::default(),
+            recorder: None,
+            input_recorder: None,
+            player: None,
+            replay_fast: false,
+            replay_current_props: false,
+
+            gesture_layer: None,
+            gesture_blot_start: 0,
+            pen_pressure: None,
+
+            selection: None,
 
             paused: true,
             contain_growth: false,
@@ -87,6 +184,10 @@ impl Default for AppState {
 
 impl eframe::App for AppState {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Wall-clock time since the last frame, consumed in fixed steps below
+        let now = Instant::now();
+        let frame_dt = now.duration_since(self.last_frame).as_secs_f64();
+        self.last_frame = now;
 
         // Handle destroy request
         if self.should_destroy {
@@ -100,119 +201,1284 @@ impl eframe::App for AppState {
             return;
         }
 
+        // Undo / redo shortcuts (shift variant first so it isn't eaten by plain Ctrl+Z)
+        let redo = ctx.input_mut(|i| {
+            i.consume_shortcut(&egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            ))
+        });
+        let undo = ctx.input_mut(|i| {
+            i.consume_shortcut(&egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND,
+                egui::Key::Z,
+            ))
+        });
+        if redo {
+            self.redo();
+        } else if undo {
+            self.undo();
+        }
+
+        // Selection shortcuts, unless a text field has focus
+        if self.shown_selection().is_some() && !ctx.wants_keyboard_input() {
+            let (delete, duplicate, deselect) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Delete)
+                        || i.consume_key(egui::Modifiers::NONE, egui::Key::Backspace),
+                    i.consume_shortcut(&egui::KeyboardShortcut::new(
+                        egui::Modifiers::COMMAND,
+                        egui::Key::D,
+                    )),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+                )
+            });
+            if delete {
+                self.delete_selection();
+            } else if duplicate {
+                self.duplicate_selection();
+            } else if deselect {
+                self.clear_selection();
+            }
+        }
+
+        // Clipboard shortcuts, unless a text field has focus
+        if !ctx.wants_keyboard_input() {
+            let events: Vec<egui::Event> = ctx.input(|i| {
+                i.events
+                    .iter()
+                    .filter(|e| {
+                        matches!(e, egui::Event::Copy | egui::Event::Cut | egui::Event::Paste(_))
+                    })
+                    .cloned()
+                    .collect()
+            });
+            for event in events {
+                match event {
+                    egui::Event::Copy => self.copy(ctx),
+                    egui::Event::Cut => self.cut(ctx),
+                    egui::Event::Paste(text) => {
+                        let at = ctx
+                            .pointer_hover_pos()
+                            .filter(|p| self.canvas_rect.contains(*p))
+                            .map(|p| self.camera.screen_to_world(self.canvas_rect, p))
+                            .unwrap_or_else(|| {
+                                self.camera.visible_world(self.canvas_rect).center()
+                            });
+                        self.paste(&text, at);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Track pen/touch force; devices without pressure never report it
+        ctx.input(|i| {
+            for event in &i.events {
+                if let egui::Event::Touch { phase, force, .. } = event {
+                    self.pen_pressure = match phase {
+                        egui::TouchPhase::End | egui::TouchPhase::Cancel => None,
+                        _ => *force,
+                    };
+                }
+            }
+        });
+
         // --- UI ---
         ui::top_bar::show(self, ctx);
+        ui::layers_panel::show(self, ctx);
 
         // --- Canvas Panel ---
         egui::CentralPanel::default()
             .frame(egui::Frame::NONE.fill(self.canvas_bg))
             .show(ctx, |ui| {
                 let rect = ui.available_rect_before_wrap();
+                self.canvas_rect = rect;
                 let (_, response) =
                     ui.allocate_exact_size(ui.available_size(), egui::Sense::drag());
 
-                let pointer_pos = response.interact_pointer_pos();
-                let is_down = response.dragged();
-                let is_released = response.drag_stopped();
+                // ---------- VIEW ----------
+                // Wheel and pinch zoom around the pointer
+                if let Some(hover) = response.hover_pos() {
+                    let (scroll, pinch) =
+                        ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+                    let factor = pinch * (scroll * 0.002).exp();
+                    if factor != 1.0 {
+                        self.camera.zoom_at(rect, hover, factor);
+                    }
+                }
 
-                // Start stroke for blotter
-                if let BrushKind::Blotter = self.active_brush {
-                    if response.drag_started() {
-                        if let Some(pos) = pointer_pos {
-                            self.blotter.begin_stroke(pos);
-                        }
+                // Middle-drag or Space+drag pans instead of painting
+                if response.drag_started() {
+                    let space = ui.input(|i| i.key_down(egui::Key::Space));
+                    self.panning = space || response.drag_started_by(egui::PointerButton::Middle);
+                }
+                if self.panning {
+                    if response.dragged() {
+                        self.camera.pan_by(response.drag_delta());
+                    }
+                    if response.drag_stopped() {
+                        self.panning = false;
                     }
                 }
 
+                let pointer_pos = response
+                    .interact_pointer_pos()
+                    .map(|p| self.camera.screen_to_world(rect, p));
+                let painting = !self.panning && self.player.is_none();
+                let is_down = painting && response.dragged();
+                let is_released = painting && response.drag_stopped();
+
                 // ---------- DRAWING ----------
+                if painting && response.drag_started() {
+                    if let Some(pos) = pointer_pos {
+                        self.pointer_down(pos);
+                    }
+                }
+
                 if is_down {
                     if let Some(pos) = pointer_pos {
-                        match self.active_brush {
-                            BrushKind::Crystal | BrushKind::Drip => {
-                                if self.current_points.last() != Some(&pos) {
-                                    self.current_points.push(pos);
-                                }
-                            }
-
-                            BrushKind::Blotter => {
-                                let new_blots = self.blotter.tick(
-                                    pos,
-                                    &self.blotter_props,
-                                    self.current_color,
-                                );
-                                self.blots.extend(new_blots);
-                            }
-                        }
+                        self.pointer_drag(pos);
                     }
                 }
 
                 // ---------- FINISH STROKE ----------
                 if is_released {
-                    match self.active_brush {
-                        BrushKind::Crystal => {
-                            if self.current_points.len() >= 2 {
-                                let mut data =
-                                    crystal::StrokeData::new(self.current_color, self.auto_grow);
-
-                                for w in self.current_points.windows(2) {
-                                    let a = w[0];
-                                    let b = w[1];
-                                    let dv = b - a;
-                                    let dir = if dv.length_sq() > 1e-6 {
-                                        dv.normalized()
-                                    } else {
-                                        egui::Vec2::new(1.0, 0.0)
-                                    };
-                                    data.add_segment(a, b, dir);
-                                }
-                                self.strokes.push(data);
-                            }
-                        }
-
-                        BrushKind::Drip => {
-                            // Drip draws live; no commit needed
-                        }
-
-                        BrushKind::Blotter => {
-                            self.blotter.end_stroke();
-                        }
-                    }
-
-                    self.current_points.clear();
+                    self.pointer_up();
                 }
 
                 // ---------- PAINT ----------
+                for layer in &mut self.layers {
+                    layer.index.sync(&layer.strokes, &layer.blots);
+                }
                 let painter = ui.painter();
+                let to_screen = self.camera.world_to_screen(rect);
 
-                CanvasPainter::paint_background(&painter, rect, self.canvas_bg);
-                CanvasPainter::paint_strokes(&painter, &self.strokes, 2.0);
-                CanvasPainter::paint_blots(&painter, &self.blots);
-                CanvasPainter::paint_active_path(&painter, &self.current_points);
-                CanvasPainter::paint_overlay(&painter, rect, &self.strokes, &self.blots);
-
-                // ---------- AUTO GROW ----------
-                if !self.paused {
-                    let now = Instant::now();
-                    if now.duration_since(self.last_tick).as_millis() > 16 {
-                        self.crystal.growth_step(
-                            &mut self.strokes,
-                            self.growth_speed,
-                            self.contain_growth,
-                        );
-                        self.last_tick = now;
-                    }
+                CanvasPainter::paint_background(painter, rect, self.canvas_bg);
+                for layer in self.layers.iter().filter(|l| l.visible) {
+                    CanvasPainter::paint_layer(painter, to_screen, layer, STROKE_BASE_SIZE);
+                }
+                if let Some(brush) = self.brushes.get(self.active_brush) {
+                    CanvasPainter::paint_active_path(painter, to_screen, brush.preview());
+                }
+                if let Some(selection) = self.shown_selection() {
+                    CanvasPainter::paint_selection(
+                        painter,
+                        to_screen,
+                        self.active_layer(),
+                        selection,
+                    );
                 }
+                if let Some(bounds) = self.contain_bounds() {
+                    CanvasPainter::paint_bounds(painter, to_screen, bounds);
+                }
+                let active = self.active_layer();
+                CanvasPainter::paint_overlay(painter, rect, &active.strokes, &active.blots);
             });
 
+        // ---------- SIMULATION ----------
+        // Containment starts out around the view it was turned on in
+        if self.contain_growth && self.sim.bounds.is_none() {
+            self.contain_to_view();
+        }
+        let contain = self.contain_bounds();
+        if self.player.as_ref().is_some_and(|p| p.fast) {
+            for _ in 0..FAST_REPLAY_STEPS {
+                if self.player.is_none() {
+                    break;
+                }
+                self.tick(contain);
+            }
+        }
+        for _ in 0..self.sim.advance(frame_dt) {
+            self.tick(contain);
+        }
+
         ctx.request_repaint();
     }
 }
 
 impl AppState {
+    /// Clear the active layer.
     pub fn destroy_canvas(&mut self) {
-        self.strokes.clear();
-        self.blots.clear();
-        self.current_points.clear();
+        self.record_input(InputEvent::Destroy);
+        let layer = &mut self.layers[self.active_layer];
+        if layer.locked {
+            self.status = Some(format!("\"{}\" is locked", layer.name));
+            return;
+        }
+        if !layer.is_empty() {
+            self.history.push(Operation::Destroyed {
+                layer: layer.id,
+                strokes: std::mem::take(&mut layer.strokes),
+                blots: std::mem::take(&mut layer.blots),
+                drips: std::mem::take(&mut layer.drips),
+            });
+            layer.index.invalidate();
+            self.selection = None;
+        }
+    }
+
+    pub fn active_layer(&self) -> &Layer {
+        &self.layers[self.active_layer]
+    }
+
+    fn layer_mut(&mut self, id: u32) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|l| l.id == id)
+    }
+
+    /// Make the layer at `index` active.
+    pub fn select_layer(&mut self, index: usize) {
+        if index >= self.layers.len() || index == self.active_layer {
+            return;
+        }
+        self.record_input(InputEvent::ActiveLayer { index });
+        self.active_layer = index;
+    }
+
+    /// Turn the `flag` setting of the layer at `index` on or off.
+    pub fn set_layer_flag(&mut self, index: usize, flag: LayerFlag, on: bool) {
+        self.record_input(InputEvent::LayerFlag { index, flag, on });
+        if let Some(layer) = self.layers.get_mut(index) {
+            *layer.flag_mut(flag) = on;
+        }
+    }
+
+    /// Add an empty layer above the active one and make it active.
+    pub fn add_layer(&mut self) {
+        self.record_input(InputEvent::AddLayer);
+        let id = self.next_layer_id;
+        self.next_layer_id += 1;
+        let layer = Layer::new(id, format!("Layer {}", id + 1));
+
+        let index = self.active_layer + 1;
+        self.history.push(Operation::LayerAdded {
+            index,
+            layer: Box::new(layer.clone()),
+        });
+        self.layers.insert(index, layer);
+        self.active_layer = index;
+    }
+
+    /// Delete the active layer. The last remaining layer can't be deleted.
+    pub fn delete_active_layer(&mut self) {
+        self.record_input(InputEvent::DeleteLayer);
+        if self.layers.len() <= 1 {
+            return;
+        }
+        let index = self.active_layer;
+        let layer = self.layers.remove(index);
+        self.history.push(Operation::LayerRemoved {
+            index,
+            layer: Box::new(layer),
+        });
+        self.active_layer = index.min(self.layers.len() - 1);
+    }
+
+    /// Move the active layer `offset` steps up (+) or down (-) the stack.
+    pub fn move_active_layer(&mut self, offset: isize) {
+        self.record_input(InputEvent::MoveLayer { offset });
+        let from = self.active_layer;
+        let to = from.saturating_add_signed(offset).min(self.layers.len() - 1);
+        if to == from {
+            return;
+        }
+        let layer = self.layers.remove(from);
+        self.layers.insert(to, layer);
+        self.history.push(Operation::LayerMoved { from, to });
+        self.active_layer = to;
+    }
+
+    /// Merge the active layer into the one below it.
+    pub fn merge_active_down(&mut self) {
+        self.record_input(InputEvent::MergeDown);
+        let index = self.active_layer;
+        if index == 0 {
+            return;
+        }
+        let upper = self.layers.remove(index);
+        let lower = self.layers[index - 1].clone();
+        self.history.push(Operation::LayersMerged {
+            index,
+            upper: Box::new(upper.clone()),
+            lower: Box::new(lower),
+        });
+        self.layers[index - 1].absorb(upper);
+        self.active_layer = index - 1;
+    }
+
+    /// Bounds growth is kept inside, if "Contain" is on.
+    pub fn contain_bounds(&self) -> Option<Rect> {
+        self.sim.bounds.filter(|_| self.contain_growth)
+    }
+
+    /// Contain growth to the area currently in view.
+    pub fn contain_to_view(&mut self) {
+        self.sim.bounds = Some(self.camera.visible_world(self.canvas_rect));
+    }
+
+    /// One fixed step of the app: replayed input that is due, the recorded
+    /// growth controls, the simulation itself and the time-lapse.
+    pub fn tick(&mut self, contain: Option<Rect>) {
+        let mut contain = contain;
+        if let Some(mut player) = self.player.take() {
+            self.play_due_input(&mut player);
+            contain = player.contain;
+            self.player = Some(player);
+        }
+
+        if let Some(recorder) = &mut self.input_recorder {
+            let controls = Controls {
+                paused: self.paused,
+                growth_speed: self.growth_speed,
+                contain,
+            };
+            recorder.note_controls(self.sim.step, controls);
+        }
+
+        self.step_simulation(contain);
+        self.record_timelapse_frame();
+
+        // Input recorded after the last step still belongs to the replay
+        if let Some(mut player) = self.player.take() {
+            if player.is_finished(self.sim.step) {
+                self.play_due_input(&mut player);
+                self.status = Some("Playback finished".to_owned());
+            } else {
+                self.player = Some(player);
+            }
+        }
+    }
+
+    /// Perform the replayed input due before the current step.
+    fn play_due_input(&mut self, player: &mut Player) {
+        for event in player.due(self.sim.step) {
+            if let InputEvent::Controls(controls) = &event {
+                player.contain = controls.contain;
+            }
+            self.apply_input(event);
+        }
+    }
+
+    /// Advance every layer by one fixed simulation step. Growth only runs
+    /// when unpaused; drips keep flowing either way.
+    pub fn step_simulation(&mut self, contain: Option<Rect>) {
+        let growth = (!self.paused).then_some(self.growth_speed);
+        let time = self.sim.time();
+
+        for layer in &mut self.layers {
+            layer.index.sync(&layer.strokes, &layer.blots);
+            let tick = TickContext {
+                dt: TIMESTEP as f32,
+                time,
+                growth: growth.filter(|_| layer.grow),
+                contain,
+                seed: self.sim.step_seed(layer.id as u64),
+            };
+            let mut canvas = CanvasMut {
+                strokes: &mut layer.strokes,
+                drips: &mut layer.drips,
+                blots: &layer.blots,
+                index: &layer.index,
+            };
+
+            let mut reshaped = false;
+            for brush in self.brushes.iter_mut() {
+                reshaped |= brush.tick(&mut canvas, &tick);
+            }
+            if reshaped {
+                layer.index.invalidate_segments();
+
+                // Strokes that decayed to nothing
+                let removed: Vec<usize> = (0..layer.strokes.len())
+                    .filter(|&i| layer.strokes[i].segments.is_empty())
+                    .collect();
+                if !removed.is_empty() {
+                    layer.strokes.retain(|s| !s.segments.is_empty());
+                    layer.index.invalidate();
+                    self.history.strokes_pruned(layer.id, &removed);
+                    if let Some(selection) = &mut self.selection {
+                        if selection.layer == layer.id {
+                            selection.strokes_pruned(&removed);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.sim.step += 1;
+    }
+
+    fn brush_context(&mut self) -> BrushContext {
+        BrushContext {
+            color: self.current_color,
+            time: self.sim.time(),
+            pressure: self.pen_pressure,
+            seed: self.sim.next_event_seed(),
+        }
+    }
+
+    /// Start a gesture with the active brush on the active layer.
+    pub fn pointer_down(&mut self, pos: Pos2) {
+        self.record_brush_setup();
+        self.record_input(InputEvent::PointerDown {
+            pos,
+            pressure: self.pen_pressure,
+        });
+
+        let layer = &self.layers[self.active_layer];
+        if layer.locked || !layer.visible {
+            self.status = Some(format!(
+                "\"{}\" is {}",
+                layer.name,
+                if layer.locked { "locked" } else { "hidden" }
+            ));
+            self.gesture_layer = None;
+            return;
+        }
+        self.gesture_layer = Some(layer.id);
+        self.gesture_blot_start = layer.blots.len();
+
+        let ctx = self.brush_context();
+        if let Some(brush) = self.brushes.get_mut(self.active_brush) {
+            let elements = brush.begin(&ctx, pos);
+            self.commit_elements(elements);
+        }
+        self.edit_layer(false);
+    }
+
+    /// Continue the current gesture.
+    pub fn pointer_drag(&mut self, pos: Pos2) {
+        self.record_input(InputEvent::PointerDrag {
+            pos,
+            pressure: self.pen_pressure,
+        });
+        if self.gesture_layer.is_none() {
+            return;
+        }
+        let ctx = self.brush_context();
+        if let Some(brush) = self.brushes.get_mut(self.active_brush) {
+            let elements = brush.drag(&ctx, pos);
+            self.commit_elements(elements);
+        }
+        self.edit_layer(false);
+    }
+
+    /// Finish the current gesture; blots laid down during it become one undo
+    /// step.
+    pub fn pointer_up(&mut self) {
+        self.record_input(InputEvent::PointerUp);
+        if self.gesture_layer.is_none() {
+            return;
+        }
+        let ctx = self.brush_context();
+        if let Some(brush) = self.brushes.get_mut(self.active_brush) {
+            let elements = brush.end(&ctx);
+            self.commit_elements(elements);
+        }
+        self.edit_layer(true);
+
+        let Some(id) = self.gesture_layer.take() else {
+            return;
+        };
+        let start = self.gesture_blot_start;
+        let Some(layer) = self.layer_mut(id) else {
+            return;
+        };
+        let start = start.min(layer.blots.len());
+        if start < layer.blots.len() {
+            let blots = layer.blots[start..].to_vec();
+            self.history.push(Operation::BlotsAdded {
+                layer: id,
+                start,
+                blots,
+            });
+        }
+    }
+
+    /// Store elements produced by a brush on the gesture's layer.
+    fn commit_elements(&mut self, elements: Vec<CanvasElement>) {
+        let Some(id) = self.gesture_layer else {
+            return;
+        };
+        let Some(layer) = self.layers.iter_mut().find(|l| l.id == id) else {
+            return;
+        };
+
+        for element in elements {
+            match element {
+                CanvasElement::Stroke(stroke) => {
+                    self.history.push(Operation::StrokeCommitted {
+                        layer: id,
+                        index: layer.strokes.len(),
+                        stroke: stroke.clone(),
+                    });
+                    layer.strokes.push(stroke);
+                }
+                CanvasElement::Drip(drip) => {
+                    self.history.push(Operation::DripCommitted {
+                        layer: id,
+                        index: layer.drips.len(),
+                        drip: drip.clone(),
+                    });
+                    layer.drips.push(drip);
+                }
+                CanvasElement::Blots(blots) => {
+                    // Recorded as a whole when the gesture ends.
+                    layer.blots.extend(blots);
+                }
+            }
+        }
+    }
+
+    /// Let the active brush edit the gesture's layer in place.
+    fn edit_layer(&mut self, finished: bool) {
+        let Some(id) = self.gesture_layer else {
+            return;
+        };
+        let Some(layer) = self.layers.iter_mut().find(|l| l.id == id) else {
+            return;
+        };
+        if let Some(brush) = self.brushes.get_mut(self.active_brush) {
+            brush.edit(&mut LayerEdit {
+                layer,
+                selection: &mut self.selection,
+                history: &mut self.history,
+                zoom: self.camera.zoom,
+                finished,
+            });
+        }
+    }
+
+    /// The selection, if it is on the active layer.
+    pub fn active_selection(&self) -> Option<&Selection> {
+        let id = self.active_layer().id;
+        self.selection.as_ref().filter(|s| s.layer == id)
+    }
+
+    /// The selection, if it is on the active layer and the active tool shows it.
+    pub fn shown_selection(&self) -> Option<&Selection> {
+        self.active_selection()
+            .filter(|_| self.brushes.get(self.active_brush).is_some_and(|b| b.uses_selection()))
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.record_input(InputEvent::ClearSelection);
+        self.selection = None;
+    }
+
+    /// Remove the selected elements.
+    pub fn delete_selection(&mut self) {
+        self.record_input(InputEvent::DeleteSelection);
+        self.edit_selection(|selection, layer| {
+            selection.remove_from(layer);
+            None
+        });
+    }
+
+    /// Copy the selected elements next to the originals and select the copies.
+    pub fn duplicate_selection(&mut self) {
+        self.record_input(InputEvent::DuplicateSelection);
+        self.edit_selection(|selection, layer| {
+            Some(selection.duplicate_in(layer, DUPLICATE_OFFSET))
+        });
+    }
+
+    /// Apply `edit` to the selection's layer, which must be the active one,
+    /// and record the change as one undo step. `edit` returns the new selection.
+    fn edit_selection(
+        &mut self,
+        edit: impl FnOnce(&Selection, &mut Layer) -> Option<Selection>,
+    ) {
+        let id = self.active_layer().id;
+        let Some(selection) = self.selection.take_if(|s| s.layer == id) else {
+            return;
+        };
+        let layer = &mut self.layers[self.active_layer];
+        if layer.locked {
+            self.status = Some(format!("\"{}\" is locked", layer.name));
+            self.selection = Some(selection);
+            return;
+        }
+
+        let (strokes_before, blots_before) = (layer.strokes.clone(), layer.blots.clone());
+        self.selection = edit(&selection, layer);
+        layer.index.invalidate();
+        let (strokes_after, blots_after) = (layer.strokes.clone(), layer.blots.clone());
+        self.history.push(Operation::Edited {
+            layer: id,
+            strokes_before,
+            blots_before,
+            strokes_after,
+            blots_after,
+            drips_start: 0,
+            drips_added: Vec::new(),
+        });
+    }
+
+    /// Selection the clipboard acts on: the one shown by the active tool.
+    fn clipboard_selection(&self) -> Option<&Selection> {
+        self.shown_selection()
+    }
+
+    /// Selected elements, or the whole active layer, as a clipboard fragment.
+    fn copy_fragment(&self) -> Option<Fragment> {
+        let fragment = Fragment::copy(
+            self.active_layer(),
+            self.clipboard_selection(),
+            self.sim.time(),
+        );
+        (!fragment.is_empty()).then_some(fragment)
+    }
+
+    /// Put the selected elements (or the whole active layer) on the
+    /// clipboard, for pasting here or into another document.
+    pub fn copy(&mut self, ctx: &egui::Context) {
+        let fragment = self.copy_fragment();
+        self.put_on_clipboard(ctx, fragment);
+    }
+
+    /// Put everything on the visible layers on the clipboard as one fragment.
+    pub fn copy_canvas(&mut self, ctx: &egui::Context) {
+        let fragment = Fragment::copy_canvas(&self.layers, self.sim.time());
+        self.put_on_clipboard(ctx, Some(fragment).filter(|f| !f.is_empty()));
+    }
+
+    fn put_on_clipboard(&mut self, ctx: &egui::Context, fragment: Option<Fragment>) {
+        let Some(fragment) = fragment else {
+            self.status = Some("Nothing to copy".to_owned());
+            return;
+        };
+        match fragment.to_json() {
+            Ok(text) => {
+                ctx.copy_text(text);
+                self.status = Some(format!(
+                    "Copied {} strokes, {} blots, {} drips",
+                    fragment.strokes.len(),
+                    fragment.blots.len(),
+                    fragment.drips.len()
+                ));
+            }
+            Err(e) => self.status = Some(format!("Copy failed: {e}")),
+        }
+    }
+
+    /// Copy the selected elements, then remove them. Without a selection
+    /// nothing is cut; copying a whole layer is left to `copy`.
+    pub fn cut(&mut self, ctx: &egui::Context) {
+        if self.clipboard_selection().is_none() {
+            self.status = Some("Select something to cut".to_owned());
+            return;
+        }
+        self.copy(ctx);
+        self.remove_copied();
+    }
+
+    /// The removing half of a cut.
+    fn remove_copied(&mut self) {
+        // Whether a selection is cut depends on the active brush
+        self.record_brush_setup();
+        self.record_input(InputEvent::Cut);
+        if self.clipboard_selection().is_none() || self.copy_fragment().is_none() {
+            return;
+        }
+        self.edit_selection(|selection, layer| {
+            selection.remove_from(layer);
+            None
+        });
+    }
+
+    /// Put a rendering of the selected elements (or the whole active layer)
+    /// on the clipboard as an image, for other applications.
+    pub fn copy_image(&mut self, ctx: &egui::Context) {
+        let image = self
+            .copy_fragment()
+            .and_then(|f| f.render(self.export_scale, STROKE_BASE_SIZE));
+        match image {
+            Some(image) => {
+                self.status = Some(format!(
+                    "Copied {}×{} image",
+                    image.size[0], image.size[1]
+                ));
+                ctx.copy_image(image);
+            }
+            None => self.status = Some("Nothing to copy".to_owned()),
+        }
+    }
+
+    /// Paste clipboard `text` holding a fragment onto the active layer,
+    /// centered on `at`, and select the pasted elements.
+    pub fn paste(&mut self, text: &str, at: Pos2) {
+        let Some(mut fragment) = Fragment::from_json(text) else {
+            self.status = Some("The clipboard holds no canvas elements".to_owned());
+            return;
+        };
+        self.record_input(InputEvent::Paste {
+            text: text.to_owned(),
+            at,
+        });
+        fragment.place(at, self.sim.time());
+
+        let layer = &mut self.layers[self.active_layer];
+        if layer.locked {
+            self.status = Some(format!("\"{}\" is locked", layer.name));
+            return;
+        }
+        let (strokes_before, blots_before) = (layer.strokes.clone(), layer.blots.clone());
+        let pasted = Selection {
+            layer: layer.id,
+            strokes: (0..fragment.strokes.len()).map(|i| layer.strokes.len() + i).collect(),
+            blots: (0..fragment.blots.len()).map(|i| layer.blots.len() + i).collect(),
+        };
+        let drips_start = layer.drips.len();
+        layer.strokes.extend(fragment.strokes);
+        layer.blots.extend(fragment.blots);
+        layer.drips.extend(fragment.drips.iter().cloned());
+        layer.index.invalidate();
+
+        self.status = Some(format!(
+            "Pasted {} strokes, {} blots, {} drips",
+            pasted.strokes.len(),
+            pasted.blots.len(),
+            fragment.drips.len()
+        ));
+        self.history.push(Operation::Edited {
+            layer: layer.id,
+            strokes_before,
+            blots_before,
+            strokes_after: layer.strokes.clone(),
+            blots_after: layer.blots.clone(),
+            drips_start,
+            drips_added: fragment.drips,
+        });
+        self.selection = Some(pasted);
+    }
+
+    /// Change the canvas background and record it for undo.
+    pub fn set_canvas_color(&mut self, color: Color32) {
+        if color != self.canvas_bg {
+            self.record_input(InputEvent::CanvasColor {
+                color,
+                continues: self.history.in_color_edit(),
+            });
+            self.history.push(Operation::CanvasColorChanged {
+                before: self.canvas_bg,
+                after: color,
+            });
+            self.canvas_bg = color;
+        }
+    }
+
+    /// Revert the most recent canvas operation.
+    pub fn undo(&mut self) {
+        self.record_input(InputEvent::Undo);
+        let Some(op) = self.history.undo() else {
+            return;
+        };
+        self.selection = None;
+
+        match op {
+            Operation::StrokeCommitted { layer, index, .. } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    if index < layer.strokes.len() {
+                        layer.strokes.remove(index);
+                    }
+                    layer.index.invalidate();
+                }
+            }
+            Operation::DripCommitted { layer, index, .. } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    if index < layer.drips.len() {
+                        layer.drips.remove(index);
+                    }
+                }
+            }
+            Operation::BlotsAdded { layer, start, .. } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.blots.truncate(start);
+                    layer.index.invalidate();
+                }
+            }
+            Operation::CanvasColorChanged { before, .. } => {
+                self.canvas_bg = before;
+            }
+            Operation::Destroyed { layer, strokes, blots, drips } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.strokes = strokes;
+                    layer.blots = blots;
+                    layer.drips = drips;
+                    layer.index.invalidate();
+                }
+            }
+            Operation::Edited {
+                layer,
+                strokes_before,
+                blots_before,
+                drips_start,
+                drips_added,
+                ..
+            } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.strokes = strokes_before;
+                    layer.blots = blots_before;
+                    if !drips_added.is_empty() {
+                        layer.drips.truncate(drips_start);
+                    }
+                    layer.index.invalidate();
+                }
+            }
+            Operation::Transformed { layer, strokes, blots, transform } => {
+                if let (Some(layer), Some(inverse)) = (self.layer_mut(layer), transform.inverse()) {
+                    selection::apply_transform(layer, &strokes, &blots, &inverse);
+                }
+            }
+            Operation::LayerAdded { index, .. } => {
+                if index < self.layers.len() && self.layers.len() > 1 {
+                    self.layers.remove(index);
+                }
+            }
+            Operation::LayerRemoved { index, layer } => {
+                let index = index.min(self.layers.len());
+                self.layers.insert(index, *layer);
+                self.active_layer = index;
+            }
+            Operation::LayerMoved { from, to } => {
+                if to < self.layers.len() {
+                    let layer = self.layers.remove(to);
+                    self.layers.insert(from.min(self.layers.len()), layer);
+                }
+            }
+            Operation::LayersMerged { index, upper, lower } => {
+                if let Some(slot) = self.layers.get_mut(index - 1) {
+                    *slot = *lower;
+                }
+                let index = index.min(self.layers.len());
+                self.layers.insert(index, *upper);
+                self.active_layer = index;
+            }
+        }
+        self.active_layer = self.active_layer.min(self.layers.len() - 1);
+    }
+
+    /// Re-apply the most recently undone operation.
+    pub fn redo(&mut self) {
+        self.record_input(InputEvent::Redo);
+        let Some(op) = self.history.redo() else {
+            return;
+        };
+        self.selection = None;
+
+        match op {
+            Operation::StrokeCommitted { layer, index, stroke } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    let index = index.min(layer.strokes.len());
+                    layer.strokes.insert(index, stroke);
+                    layer.index.invalidate();
+                }
+            }
+            Operation::DripCommitted { layer, index, drip } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    let index = index.min(layer.drips.len());
+                    layer.drips.insert(index, drip);
+                }
+            }
+            Operation::BlotsAdded { layer, start, blots } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.blots.truncate(start);
+                    layer.blots.extend(blots);
+                    layer.index.invalidate();
+                }
+            }
+            Operation::CanvasColorChanged { after, .. } => {
+                self.canvas_bg = after;
+            }
+            Operation::Destroyed { layer, .. } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.strokes.clear();
+                    layer.blots.clear();
+                    layer.drips.clear();
+                    layer.index.invalidate();
+                }
+            }
+            Operation::Edited {
+                layer,
+                strokes_after,
+                blots_after,
+                drips_start,
+                drips_added,
+                ..
+            } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.strokes = strokes_after;
+                    layer.blots = blots_after;
+                    if !drips_added.is_empty() {
+                        layer.drips.truncate(drips_start);
+                        layer.drips.extend(drips_added);
+                    }
+                    layer.index.invalidate();
+                }
+            }
+            Operation::Transformed { layer, strokes, blots, transform } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    selection::apply_transform(layer, &strokes, &blots, &transform);
+                }
+            }
+            Operation::LayerAdded { index, layer } => {
+                let index = index.min(self.layers.len());
+                self.layers.insert(index, *layer);
+                self.active_layer = index;
+            }
+            Operation::LayerRemoved { index, .. } => {
+                if index < self.layers.len() && self.layers.len() > 1 {
+                    self.layers.remove(index);
+                }
+            }
+            Operation::LayerMoved { from, to } => {
+                if from < self.layers.len() {
+                    let layer = self.layers.remove(from);
+                    self.layers.insert(to.min(self.layers.len()), layer);
+                    self.active_layer = to.min(self.layers.len() - 1);
+                }
+            }
+            Operation::LayersMerged { index, upper, .. } => {
+                if index < self.layers.len() && index > 0 {
+                    self.layers.remove(index);
+                    self.layers[index - 1].absorb(*upper);
+                    self.active_layer = index - 1;
+                }
+            }
+        }
+        self.active_layer = self.active_layer.min(self.layers.len() - 1);
+    }
+
+    /// Load a project file, replacing the current canvas.
+    pub fn open_document(&mut self, path: PathBuf) {
+        match Document::load(&path) {
+            Ok(doc) => {
+                doc.apply_to(self);
+                self.history = History::new();
+                self.status = Some(format!("Opened {}", path.display()));
+                self.document_path = Some(path);
+            }
+            Err(e) => {
+                self.status = Some(format!("Open failed: {e}"));
+            }
+        }
+    }
+
+    /// Write the current canvas to `path` and remember it for later saves.
+    pub fn save_document(&mut self, path: PathBuf) {
+        match Document::from_state(self).save(&path) {
+            Ok(()) => {
+                self.status = Some(format!("Saved {}", path.display()));
+                self.document_path = Some(path);
+            }
+            Err(e) => {
+                self.status = Some(format!("Save failed: {e}"));
+            }
+        }
+    }
+
+    /// Store the active brush's properties as preset `name` and persist the library.
+    pub fn save_preset(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            self.status = Some("Preset needs a name".to_owned());
+            return;
+        }
+        if !self.presets.store(self.active_brush, name, &self.brushes) {
+            return;
+        }
+        self.status = Some(match self.save_user_presets() {
+            Ok(()) => format!("Saved preset \"{name}\""),
+            Err(e) => format!("Preset save failed: {e}"),
+        });
+    }
+
+    /// Persist the preset library, unless it failed to load at startup.
+    fn save_user_presets(&self) -> Result<(), PresetError> {
+        if self.presets_error.is_some() {
+            return Err(PresetError::NotLoaded);
+        }
+        self.presets.save_user()
+    }
+
+    /// Load preset `name` into the active brush.
+    pub fn apply_preset(&mut self, name: &str) {
+        if self.presets.apply(self.active_brush, name, &mut self.brushes) {
+            self.preset_name = name.to_owned();
+        }
+    }
+
+    /// Delete preset `name` of the active brush and persist the library.
+    pub fn delete_preset(&mut self, name: &str) {
+        if !self.presets.remove(self.active_brush, name) {
+            return;
+        }
+        self.status = Some(match self.save_user_presets() {
+            Ok(()) => format!("Deleted preset \"{name}\""),
+            Err(e) => format!("Preset save failed: {e}"),
+        });
+    }
+
+    /// Merge a shared preset pack into the user library.
+    pub fn import_presets(&mut self, path: PathBuf) {
+        let pack = match PresetLibrary::load(&path) {
+            Ok(pack) => pack,
+            Err(e) => {
+                self.status = Some(format!("Import failed: {e}"));
+                return;
+            }
+        };
+        let count = self.presets.merge(pack);
+        self.status = Some(match self.save_user_presets() {
+            Ok(()) => format!("Imported {count} presets from {}", path.display()),
+            Err(e) => format!("Preset save failed: {e}"),
+        });
+    }
+
+    /// Write the whole preset library as a pack for sharing.
+    pub fn export_presets(&mut self, path: PathBuf) {
+        self.status = Some(match self.presets.save(&path) {
+            Ok(()) => format!(
+                "Exported {} presets to {}",
+                self.presets.count(),
+                path.display()
+            ),
+            Err(e) => format!("Export failed: {e}"),
+        });
+    }
+
+    /// World-space bounding box of everything on the canvas.
+    pub fn content_bounds(&self) -> Option<Rect> {
+        let mut bounds = Rect::NOTHING;
+        for layer in &self.layers {
+            for seg in layer.strokes.iter().flat_map(|s| &s.segments) {
+                bounds = bounds.union(Rect::from_two_pos(seg.start, seg.end));
+            }
+            for b in &layer.blots {
+                bounds = bounds.union(Rect::from_center_size(b.pos, egui::Vec2::splat(b.radius * 2.0)));
+            }
+            for d in &layer.drips {
+                for p in d.path.iter().chain(d.runs.iter().flat_map(|r| &r.points)) {
+                    bounds.extend_with(*p);
+                }
+            }
+        }
+        bounds.is_positive().then_some(bounds)
+    }
+
+    /// Zoom and center the view on the canvas content.
+    pub fn fit_to_content(&mut self) {
+        match self.content_bounds() {
+            Some(bounds) => self.camera.fit(self.canvas_rect, bounds),
+            None => self.camera.reset(),
+        }
+    }
+
+    /// Borrow the canvas content for the offscreen renderers.
+    pub fn export_scene(&self) -> ExportScene<'_> {
+        ExportScene {
+            layers: &self.layers,
+            background: self.canvas_bg,
+            base_size: STROKE_BASE_SIZE,
+        }
+    }
+
+    /// Rasterize the visible canvas at `export_scale` and write it as PNG.
+    pub fn export_png(&mut self, path: PathBuf) {
+        let rect = self.canvas_rect;
+        let width = (rect.width() * self.export_scale).round().max(1.0) as u32;
+        let height = (rect.height() * self.export_scale).round().max(1.0) as u32;
+        let view = self.camera.visible_world(rect);
+
+        let image = raster::render_scene(&self.export_scene(), view, width, height);
+
+        self.status = Some(match image.write_png(&path) {
+            Ok(()) => format!("Exported {width}×{height} to {}", path.display()),
+            Err(e) => format!("Export failed: {e}"),
+        });
+    }
+
+    /// Write the visible canvas as SVG vector artwork.
+    pub fn export_svg(&mut self, path: PathBuf) {
+        let view = self.camera.visible_world(self.canvas_rect);
+        let doc = svg::render_svg(&self.export_scene(), view);
+
+        self.status = Some(match svg::write_svg(&path, &doc) {
+            Ok(()) => format!("Exported SVG to {}", path.display()),
+            Err(e) => format!("Export failed: {e}"),
+        });
+    }
+
+    /// Start recording the visible canvas at `export_scale`; the first frame
+    /// is taken now, the rest as the simulation steps.
+    pub fn start_timelapse(&mut self, path: PathBuf) {
+        self.stop_timelapse();
+
+        let rect = self.canvas_rect;
+        let width = (rect.width() * self.export_scale).round().max(1.0) as u32;
+        let height = (rect.height() * self.export_scale).round().max(1.0) as u32;
+        let view = self.camera.visible_world(rect);
+
+        match Recorder::start(&path, self.timelapse.clone(), view, width, height) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                self.status = Some(format!("Recording time-lapse to {}", path.display()));
+                self.record_timelapse_frame();
+            }
+            Err(e) => self.status = Some(format!("Time-lapse failed: {e}")),
+        }
+    }
+
+    /// Finish the recording in progress, keeping the frames written so far.
+    pub fn stop_timelapse(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let path = recorder.path.clone();
+        self.status = Some(match recorder.finish() {
+            Ok(frames) => format!("Saved {frames} time-lapse frames to {}", path.display()),
+            Err(e) => format!("Time-lapse failed: {e}"),
+        });
+    }
+
+    /// Let the recorder take a frame if one is due after this step.
+    fn record_timelapse_frame(&mut self) {
+        let Some(mut recorder) = self.recorder.take() else {
+            return;
+        };
+        match recorder.on_step(&self.export_scene()) {
+            Ok(false) => self.recorder = Some(recorder),
+            Ok(true) => {
+                self.recorder = Some(recorder);
+                self.stop_timelapse();
+            }
+            Err(e) => self.status = Some(format!("Time-lapse failed: {e}")),
+        }
+    }
+
+    /// Start recording input on top of a snapshot of the current document.
+    pub fn start_input_recording(&mut self) {
+        if self.player.is_some() {
+            self.status = Some("Can't record during playback".to_owned());
+            return;
+        }
+        self.input_recorder = Some(InputRecorder::new(Document::from_state(self), self.sim.step));
+        self.status = Some("Recording input".to_owned());
+    }
+
+    /// Stop recording input and write the replay to `path`.
+    pub fn stop_input_recording(&mut self, path: PathBuf) {
+        let Some(recorder) = self.input_recorder.take() else {
+            return;
+        };
+        let recording = recorder.finish(self.sim.step);
+        self.status = Some(match recording.save(&path) {
+            Ok(()) => format!(
+                "Saved {} input events to {}",
+                recording.events.len(),
+                path.display()
+            ),
+            Err(e) => format!("Replay save failed: {e}"),
+        });
+    }
+
+    /// Load a replay from `path` and play it with the current replay options.
+    pub fn start_playback(&mut self, path: PathBuf) {
+        if self.input_recorder.is_some() {
+            self.status = Some("Stop recording before playing a replay".to_owned());
+            return;
+        }
+        match Recording::load(&path) {
+            Ok(recording) => {
+                let brushes = self.replay_current_props.then(|| Document::from_state(self));
+                self.play(recording, self.replay_fast);
+                if let Some(doc) = brushes {
+                    doc.apply_brush_props(self);
+                }
+                self.status = Some(format!("Playing {}", path.display()));
+            }
+            Err(e) => self.status = Some(format!("Replay failed: {e}")),
+        }
+    }
+
+    /// Replace the document with the recording's starting snapshot and feed
+    /// its input back as the simulation steps.
+    pub fn play(&mut self, recording: Recording, fast: bool) {
+        recording.start.clone().apply_to(self);
+        self.history = History::new();
+        self.document_path = None;
+        self.gesture_layer = None;
+        self.pen_pressure = None;
+        self.player = Some(Player::new(recording, self.sim.step, fast));
+    }
+
+    pub fn stop_playback(&mut self) {
+        if self.player.take().is_some() {
+            self.pen_pressure = None;
+            self.status = Some("Playback stopped".to_owned());
+        }
+    }
+
+    /// Append `event` to the input recording, if one is running.
+    fn record_input(&mut self, event: InputEvent) {
+        if let Some(recorder) = &mut self.input_recorder {
+            recorder.push(self.sim.step, event);
+        }
+    }
+
+    /// Record the brush and color in use, if they changed since last noted.
+    fn record_brush_setup(&mut self) {
+        if let Some(recorder) = &mut self.input_recorder {
+            let (brush, color) = (self.active_brush.0, self.current_color);
+            recorder.note_stroke_setup(self.sim.step, brush, color);
+        }
+    }
+
+    /// Perform one recorded input event.
+    fn apply_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::PointerDown { pos, pressure } => {
+                self.pen_pressure = pressure;
+                self.pointer_down(pos);
+            }
+            InputEvent::PointerDrag { pos, pressure } => {
+                self.pen_pressure = pressure;
+                self.pointer_drag(pos);
+            }
+            InputEvent::PointerUp => self.pointer_up(),
+            InputEvent::Brush { kind } => {
+                if let Some(kind) = self.brushes.kind_by_id(&kind) {
+                    self.active_brush = kind;
+                }
+            }
+            InputEvent::Color { color } => self.current_color = color,
+            InputEvent::CanvasColor { color, continues } => {
+                if !continues {
+                    self.history.end_color_edit();
+                }
+                self.set_canvas_color(color);
+            }
+            InputEvent::ActiveLayer { index } => self.select_layer(index),
+            InputEvent::AddLayer => self.add_layer(),
+            InputEvent::DeleteLayer => self.delete_active_layer(),
+            InputEvent::MoveLayer { offset } => self.move_active_layer(offset),
+            InputEvent::MergeDown => self.merge_active_down(),
+            InputEvent::LayerFlag { index, flag, on } => self.set_layer_flag(index, flag, on),
+            InputEvent::Controls(controls) => {
+                self.paused = controls.paused;
+                self.growth_speed = controls.growth_speed;
+            }
+            InputEvent::Undo => self.undo(),
+            InputEvent::Redo => self.redo(),
+            InputEvent::Destroy => self.destroy_canvas(),
+            InputEvent::ClearSelection => self.clear_selection(),
+            InputEvent::DeleteSelection => self.delete_selection(),
+            InputEvent::DuplicateSelection => self.duplicate_selection(),
+            InputEvent::Cut => self.remove_copied(),
+            InputEvent::Paste { text, at } => self.paste(&text, at),
+        }
     }
 
     pub fn exit_request(&mut self) {
@@ -0,0 +1,358 @@
+// app/ui/brush_props.rs
+//! Editable property panels for the built-in brushes, each with a live tip preview.
+
+use std::f32::consts::FRAC_PI_2;
+
+use eframe::egui::{self, Color32, Mesh, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2};
+use eframe::emath::Rot2;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::app::blend::BlendMode;
+use crate::app::brushes::blotter_props::BlotterProps;
+use crate::app::brushes::crystal_props::{CrystalProps, Smoothing};
+use crate::app::brushes::drip_props::DripProps;
+use crate::app::brushes::eraser_props::EraserProps;
+use crate::app::brushes::select_props::{MarqueeShape, SelectProps};
+use crate::app::painter::CanvasPainter;
+use crate::app::ribbon::Ribbon;
+
+/// Size of the brush tip preview box.
+const PREVIEW_SIZE: Vec2 = Vec2::new(220.0, 70.0);
+
+/// Points along the crystal preview's stem, so its taper reads smoothly.
+const PREVIEW_STEPS: usize = 24;
+
+/// Fixed seed so jittered previews stay still between frames.
+const PREVIEW_SEED: u64 = 7;
+
+/// Background of the preview box.
+const PREVIEW_BG: Color32 = Color32::from_rgb(59, 47, 47);
+
+pub fn crystal(ui: &mut Ui, props: &mut CrystalProps, color: Color32) {
+    egui::Grid::new("crystal_props").num_columns(2).show(ui, |ui| {
+        ui.label("Branch angle");
+        ui.add(
+            egui::Slider::new(&mut props.branch_angle, 0.0..=FRAC_PI_2)
+                .custom_formatter(|v, _| format!("{:.0}°", v.to_degrees())),
+        );
+        ui.end_row();
+
+        ui.label("Branch decay");
+        ui.add(egui::Slider::new(&mut props.branch_decay, 0.0..=1.0));
+        ui.end_row();
+
+        ui.label("Min segment");
+        ui.add(egui::Slider::new(&mut props.min_segment, 1.0..=50.0).suffix(" pt"));
+        ui.end_row();
+
+        ui.label("Thickness");
+        ui.add(egui::Slider::new(&mut props.thickness, 0.5..=12.0).suffix(" pt"));
+        ui.end_row();
+
+        ui.label("Decay fade");
+        ui.add(egui::Slider::new(&mut props.decay_fade, 0.0..=4.0))
+            .on_hover_text("How fast crystals fade while decaying; older parts fade first");
+        ui.end_row();
+    });
+
+    egui::CollapsingHeader::new("Width")
+        .id_salt("crystal_width")
+        .show(ui, |ui| {
+            egui::Grid::new("crystal_width_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Taper start");
+                ui.add(egui::Slider::new(&mut props.taper_start, 0.0..=200.0).suffix(" pt"));
+                ui.end_row();
+
+                ui.label("Taper end");
+                ui.add(egui::Slider::new(&mut props.taper_end, 0.0..=200.0).suffix(" pt"))
+                    .on_hover_text("Crystals keep growing from the tip at its tapered width");
+                ui.end_row();
+
+                ui.label("Branch width");
+                ui.add(egui::Slider::new(&mut props.branch_width, 0.1..=1.0))
+                    .on_hover_text("Width of each branch relative to the tip it sprouts from");
+                ui.end_row();
+
+                ui.label("Pressure → width");
+                ui.add(egui::Slider::new(&mut props.pressure_width, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Speed → width");
+                ui.add(egui::Slider::new(&mut props.speed_width, -1.0..=1.0));
+                ui.end_row();
+            });
+        });
+
+    egui::CollapsingHeader::new("Stroke input")
+        .id_salt("crystal_input")
+        .show(ui, |ui| {
+            egui::Grid::new("crystal_input_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Averaging");
+                ui.add(egui::Slider::new(&mut props.input_average, 1..=16))
+                    .on_hover_text("Pointer positions averaged together; 1 follows the pointer");
+                ui.end_row();
+
+                ui.label("Lazy radius");
+                ui.add(egui::Slider::new(&mut props.lazy_radius, 0.0..=60.0).suffix(" pt"))
+                    .on_hover_text("The stroke trails the pointer on a string this long");
+                ui.end_row();
+
+                ui.label("Smoothing");
+                egui::ComboBox::from_id_salt("crystal_smoothing_combobox")
+                    .selected_text(props.smoothing.label())
+                    .show_ui(ui, |ui| {
+                        for smoothing in Smoothing::ALL {
+                            ui.selectable_value(&mut props.smoothing, smoothing, smoothing.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Resample");
+                ui.checkbox(&mut props.resample, "")
+                    .on_hover_text("Respace the path evenly to the minimum segment length");
+                ui.end_row();
+            });
+        });
+
+    reset_button(ui, props);
+
+    let (rect, painter) = preview_area(ui);
+    let mid_y = rect.center().y;
+    let feather = 1.0 / ui.ctx().pixels_per_point();
+    let mut mesh = Mesh::default();
+
+    // Tapered main stem with branches at the configured angle and width
+    let start = Pos2::new(rect.left() + 12.0, mid_y);
+    let end = Pos2::new(rect.right() - 12.0, mid_y);
+    let trunk = end - start;
+    let width_at = |x: f32| props.thickness * props.taper(x, trunk.x);
+
+    let mut stem = Ribbon::default();
+    for i in 0..=PREVIEW_STEPS {
+        let x = trunk.x * i as f32 / PREVIEW_STEPS as f32;
+        stem.push(start + Vec2::new(x, 0.0), width_at(x), 1.0);
+    }
+    stem.tessellate(&mut mesh, feather, |_| color);
+
+    let spacing = props.min_segment.max(4.0) * 2.0;
+    let mut x = spacing;
+    let mut side = 1.0;
+    while x < trunk.x - 8.0 {
+        let origin = start + Vec2::new(x, 0.0);
+        let len = (rect.height() * 0.4).min(spacing * 1.5) * props.branch_decay.max(0.1);
+        let dir = Rot2::from_angle(-side * props.branch_angle) * Vec2::X;
+        let width = width_at(x) * props.branch_width.clamp(0.0, 1.0);
+        let mut branch = Ribbon::default();
+        branch.push(origin, width, 1.0);
+        branch.push(origin + dir * len, width, 1.0);
+        branch.tessellate(&mut mesh, feather, |_| color);
+        x += spacing;
+        side = -side;
+    }
+    painter.add(Shape::mesh(mesh));
+}
+
+pub fn drip(ui: &mut Ui, props: &mut DripProps, color: Color32) {
+    egui::Grid::new("drip_props").num_columns(2).show(ui, |ui| {
+        ui.label("Gravity");
+        ui.add(egui::Slider::new(&mut props.gravity, 0.0..=5.0));
+        ui.end_row();
+
+        ui.label("Viscosity");
+        ui.add(egui::Slider::new(&mut props.viscosity, 0.0..=0.99));
+        ui.end_row();
+
+        ui.label("Thickness");
+        ui.add(egui::Slider::new(&mut props.thickness, 0.5..=16.0).suffix(" pt"));
+        ui.end_row();
+    });
+
+    reset_button(ui, props);
+
+    let (rect, painter) = preview_area(ui);
+    let top = rect.top() + 10.0 + props.thickness * 0.5;
+    painter.line_segment(
+        [Pos2::new(rect.left() + 12.0, top), Pos2::new(rect.right() - 12.0, top)],
+        Stroke::new(props.thickness, color),
+    );
+
+    // Run length follows the terminal speed gravity and viscosity settle on
+    let terminal = props.gravity / (1.0 - props.viscosity.clamp(0.0, 0.99));
+    let room = rect.bottom() - top - 6.0;
+    for (i, share) in [0.55, 1.0, 0.35, 0.8, 0.6].iter().enumerate() {
+        let x = rect.left() + 30.0 + i as f32 * 40.0;
+        let len = (terminal * 4.0 * share).min(room);
+        if len < 1.0 {
+            continue;
+        }
+        let head = Pos2::new(x, top + len);
+        painter.line_segment(
+            [Pos2::new(x, top), head],
+            Stroke::new(props.thickness * 0.65, color),
+        );
+        painter.circle_filled(head, props.thickness * 0.35, color);
+    }
+}
+
+pub fn blotter(ui: &mut Ui, props: &mut BlotterProps, color: Color32) {
+    egui::Grid::new("blotter_props").num_columns(2).show(ui, |ui| {
+        ui.label("Radius");
+        ui.add(egui::Slider::new(&mut props.radius, 1.0..=80.0).suffix(" pt"));
+        ui.end_row();
+
+        ui.label("Softness");
+        ui.add(egui::Slider::new(&mut props.softness, 0.0..=1.0));
+        ui.end_row();
+
+        ui.label("Opacity");
+        ui.add(egui::Slider::new(&mut props.opacity, 0.0..=1.0));
+        ui.end_row();
+
+        ui.label("Spacing");
+        ui.add(egui::Slider::new(&mut props.spacing, 0.5..=50.0).suffix(" pt"));
+        ui.end_row();
+
+        ui.label("Blend");
+        egui::ComboBox::from_id_salt("blotter_blend_combobox")
+            .selected_text(props.blend.label())
+            .show_ui(ui, |ui| {
+                for mode in BlendMode::ALL {
+                    ui.selectable_value(&mut props.blend, mode, mode.label());
+                }
+            });
+        ui.end_row();
+    });
+
+    egui::CollapsingHeader::new("Dynamics")
+        .id_salt("blotter_dynamics")
+        .show(ui, |ui| {
+            egui::Grid::new("blotter_dynamics_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Full speed");
+                ui.add(egui::Slider::new(&mut props.max_speed, 100.0..=5000.0).suffix(" pt/s"));
+                ui.end_row();
+
+                ui.label("Speed curve");
+                ui.add(egui::Slider::new(&mut props.speed_curve, 0.2..=5.0).logarithmic(true));
+                ui.end_row();
+
+                ui.label("Speed → radius");
+                ui.add(egui::Slider::new(&mut props.speed_radius, -1.0..=1.0));
+                ui.end_row();
+
+                ui.label("Speed → opacity");
+                ui.add(egui::Slider::new(&mut props.speed_opacity, -1.0..=1.0));
+                ui.end_row();
+
+                ui.label("Pressure curve");
+                ui.add(egui::Slider::new(&mut props.pressure_curve, 0.2..=5.0).logarithmic(true));
+                ui.end_row();
+
+                ui.label("Pressure → radius");
+                ui.add(egui::Slider::new(&mut props.pressure_radius, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Pressure → opacity");
+                ui.add(egui::Slider::new(&mut props.pressure_opacity, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Size jitter");
+                ui.add(egui::Slider::new(&mut props.size_jitter, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Scatter");
+                ui.add(egui::Slider::new(&mut props.scatter, 0.0..=3.0).suffix("× radius"));
+                ui.end_row();
+
+                ui.label("Hue jitter");
+                ui.add(egui::Slider::new(&mut props.hue_jitter, 0.0..=180.0).suffix("°"));
+                ui.end_row();
+            });
+        });
+
+    reset_button(ui, props);
+
+    // A run of blots at the configured spacing, speeding up from left to right
+    let (rect, painter) = preview_area(ui);
+    let painter = painter.with_clip_rect(rect);
+    let scale = (rect.height() * 0.3 / props.radius.max(0.001)).min(1.0);
+    let spacing = (props.spacing.max(0.5) * scale).max(1.0);
+    let mut rng = StdRng::seed_from_u64(PREVIEW_SEED);
+    let mut x = rect.left() + 8.0;
+    while x < rect.right() - 8.0 {
+        let t = (x - rect.left()) / rect.width();
+        let mut b = props.dynamic_blot(
+            Pos2::new(x, rect.center().y),
+            color,
+            t * props.max_speed,
+            None,
+            &mut rng,
+        );
+        b.pos = Pos2::new(x, rect.center().y) + (b.pos - Pos2::new(x, rect.center().y)) * scale;
+        b.radius *= scale;
+        painter.circle_filled(b.pos, b.radius, b.blend.live_color(CanvasPainter::blot_fill(&b)));
+        if let Some(halo) = CanvasPainter::blot_halo(&b) {
+            let halo_color = b.blend.live_color(halo.color);
+            painter.circle_stroke(b.pos, halo.radius, Stroke::new(halo.width, halo_color));
+        }
+        x += spacing;
+    }
+}
+
+pub fn eraser(ui: &mut Ui, props: &mut EraserProps, _color: Color32) {
+    egui::Grid::new("eraser_props").num_columns(2).show(ui, |ui| {
+        ui.label("Radius");
+        ui.add(egui::Slider::new(&mut props.radius, 1.0..=100.0).suffix(" pt"));
+        ui.end_row();
+
+        ui.label("Erase");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut props.strokes, "Crystals");
+            ui.checkbox(&mut props.blots, "Blots");
+        });
+        ui.end_row();
+    });
+
+    reset_button(ui, props);
+
+    // Eraser footprint at its real size, clipped to the preview box
+    let (rect, painter) = preview_area(ui);
+    let painter = painter.with_clip_rect(rect);
+    let stroke = Stroke::new(1.0, Color32::from_gray(200));
+    let left = Pos2::new(rect.left() + 16.0, rect.center().y);
+    let right = Pos2::new(rect.right() - 16.0, rect.center().y);
+    painter.circle_stroke(left, props.radius, stroke);
+    painter.circle_stroke(right, props.radius, stroke);
+    for side in [-1.0, 1.0] {
+        let offset = Vec2::new(0.0, side * props.radius);
+        painter.line_segment([left + offset, right + offset], stroke);
+    }
+}
+
+pub fn select(ui: &mut Ui, props: &mut SelectProps) {
+    ui.horizontal(|ui| {
+        ui.label("Shape");
+        for shape in MarqueeShape::ALL {
+            ui.selectable_value(&mut props.shape, shape, shape.label());
+        }
+    });
+
+    ui.separator();
+    ui.label("Drag to select, click to pick one element.");
+    ui.label("Drag the box to move, a corner to scale, the knob to rotate.");
+    ui.label("Delete removes, Ctrl+D duplicates, Esc deselects.");
+}
+
+fn reset_button<T: Default>(ui: &mut Ui, props: &mut T) {
+    if ui.button("Reset to defaults").clicked() {
+        *props = T::default();
+    }
+}
+
+fn preview_area(ui: &mut Ui) -> (Rect, egui::Painter) {
+    ui.separator();
+    let (rect, _) = ui.allocate_exact_size(PREVIEW_SIZE, Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, PREVIEW_BG);
+    (rect, painter)
+}
@@ -1,7 +1,8 @@
-use eframe::egui::{self, Ui};
+use eframe::egui::Ui;
 use crate::app::state::AppState;
 
-/// Simple background color picker block.
+/// Simple background color picker block. Changes made during one press of
+/// the pointer (a drag across the picker) become a single undo step.
 pub fn draw(ui: &mut Ui, state: &mut AppState) {
     ui.group(|ui| {
         ui.label("Canvas Color");
@@ -9,7 +10,10 @@ pub fn draw(ui: &mut Ui, state: &mut AppState) {
         let mut color = state.canvas_bg;
 
         if ui.color_edit_button_srgba(&mut color).changed() {
-            state.canvas_bg = color;
+            state.set_canvas_color(color);
+        }
+        if ui.input(|i| i.pointer.any_released()) {
+            state.history.end_color_edit();
         }
     });
 }
@@ -1,5 +1,5 @@
 // src/app/ui/color_pickers.rs
-use eframe::egui::{self, Color32, Ui};
+use eframe::egui::{self, Ui};
 
 use crate::app::state::AppState;
 
@@ -0,0 +1,72 @@
+// app/ui/edit_menu.rs
+use eframe::egui::{self, Ui};
+use crate::app::state::AppState;
+
+/// Clipboard and selection commands. Copy acts on the select tool's
+/// selection, or the whole active layer when nothing is selected; cut needs
+/// a selection.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    ui.menu_button("Edit", |ui| {
+        let selected = state.shown_selection().is_some();
+        if ui
+            .add_enabled(selected, egui::Button::new("Cut"))
+            .on_hover_text("Ctrl+X")
+            .clicked()
+        {
+            ui.close();
+            state.cut(ui.ctx());
+        }
+        if ui
+            .button("Copy")
+            .on_hover_text("Ctrl+C — paste into this or another document")
+            .clicked()
+        {
+            ui.close();
+            state.copy(ui.ctx());
+        }
+        if ui
+            .button("Copy canvas")
+            .on_hover_text("Everything on the visible layers, as one fragment")
+            .clicked()
+        {
+            ui.close();
+            state.copy_canvas(ui.ctx());
+        }
+        if ui
+            .button("Copy as image")
+            .on_hover_text("For pasting into other applications")
+            .clicked()
+        {
+            ui.close();
+            state.copy_image(ui.ctx());
+        }
+        ui.label("Paste: Ctrl+V at the pointer");
+
+        ui.separator();
+
+        if ui
+            .add_enabled(selected, egui::Button::new("Duplicate"))
+            .on_hover_text("Ctrl+D")
+            .clicked()
+        {
+            ui.close();
+            state.duplicate_selection();
+        }
+        if ui
+            .add_enabled(selected, egui::Button::new("Delete"))
+            .on_hover_text("Delete")
+            .clicked()
+        {
+            ui.close();
+            state.delete_selection();
+        }
+        if ui
+            .add_enabled(selected, egui::Button::new("Select none"))
+            .on_hover_text("Esc")
+            .clicked()
+        {
+            ui.close();
+            state.clear_selection();
+        }
+    });
+}
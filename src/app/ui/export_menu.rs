@@ -0,0 +1,121 @@
+// app/ui/export_menu.rs
+use eframe::egui::{self, ComboBox, Ui};
+use crate::app::export::timelapse::TimelapseFormat;
+use crate::app::state::AppState;
+
+/// Export menu: writes the canvas out as an image file or records a
+/// time-lapse of its growth.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    ui.menu_button("Export", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Scale:");
+            ui.add(
+                egui::DragValue::new(&mut state.export_scale)
+                    .range(0.25..=8.0)
+                    .speed(0.05)
+                    .suffix("×"),
+            );
+        });
+
+        let rect = state.canvas_rect;
+        ui.label(format!(
+            "{} × {} px",
+            (rect.width() * state.export_scale).round(),
+            (rect.height() * state.export_scale).round(),
+        ));
+
+        ui.separator();
+
+        if ui.button("PNG image…").clicked() {
+            ui.close();
+            let dialog = rfd::FileDialog::new()
+                .add_filter("PNG image", &["png"])
+                .set_file_name("crystal.png");
+            if let Some(mut path) = dialog.save_file() {
+                if path.extension().is_none() {
+                    path.set_extension("png");
+                }
+                state.export_png(path);
+            }
+        }
+
+        if ui.button("SVG vector…").clicked() {
+            ui.close();
+            let dialog = rfd::FileDialog::new()
+                .add_filter("SVG image", &["svg"])
+                .set_file_name("crystal.svg");
+            if let Some(mut path) = dialog.save_file() {
+                if path.extension().is_none() {
+                    path.set_extension("svg");
+                }
+                state.export_svg(path);
+            }
+        }
+
+        ui.separator();
+        timelapse(ui, state);
+    });
+}
+
+/// Time-lapse options, and the record/stop button.
+fn timelapse(ui: &mut Ui, state: &mut AppState) {
+    ui.label("Time-lapse");
+
+    if let Some(recorder) = &state.recorder {
+        ui.label(format!(
+            "Recording frame {} of {}",
+            recorder.frames_written(),
+            recorder.frames_total()
+        ));
+        if ui.button("Stop recording").clicked() {
+            ui.close();
+            state.stop_timelapse();
+        }
+        return;
+    }
+
+    let settings = &mut state.timelapse;
+    egui::Grid::new("timelapse_grid").num_columns(2).show(ui, |ui| {
+        ui.label("Format");
+        ComboBox::from_id_salt("timelapse_format_combobox")
+            .selected_text(settings.format.label())
+            .show_ui(ui, |ui| {
+                for format in TimelapseFormat::ALL {
+                    ui.selectable_value(&mut settings.format, format, format.label());
+                }
+            });
+        ui.end_row();
+
+        ui.label("Frames");
+        ui.add(egui::DragValue::new(&mut settings.frames).range(1..=10_000));
+        ui.end_row();
+
+        ui.label("Steps per frame");
+        ui.add(egui::DragValue::new(&mut settings.every).range(1..=600));
+        ui.end_row();
+
+        if settings.format == TimelapseFormat::Gif {
+            ui.label("Frame rate");
+            ui.add(egui::DragValue::new(&mut settings.fps).range(1..=100).suffix(" fps"));
+            ui.end_row();
+        }
+    });
+
+    if ui
+        .button("Record…")
+        .on_hover_text("Frames follow the simulation; unfreeze to capture growth")
+        .clicked()
+    {
+        ui.close();
+        let format = state.timelapse.format;
+        let dialog = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("timelapse.{}", format.extension()));
+        if let Some(mut path) = dialog.save_file() {
+            if path.extension().is_none() {
+                path.set_extension(format.extension());
+            }
+            state.start_timelapse(path);
+        }
+    }
+}
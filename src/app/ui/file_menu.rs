@@ -0,0 +1,59 @@
+// app/ui/file_menu.rs
+use eframe::egui::{self, Ui};
+use crate::app::document::FILE_EXTENSION;
+use crate::app::state::AppState;
+
+/// Native file dialog preconfigured for `.crystal` projects.
+fn project_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("Crystal Painter project", &[FILE_EXTENSION])
+}
+
+/// Open / Save / Save As menu for project files.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    // Ctrl+S saves in place (or asks for a path the first time)
+    let save_shortcut = ui.ctx().input_mut(|i| {
+        i.consume_shortcut(&egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND,
+            egui::Key::S,
+        ))
+    });
+    if save_shortcut {
+        save(state);
+    }
+
+    ui.menu_button("File", |ui| {
+        if ui.button("Open…").clicked() {
+            ui.close();
+            if let Some(path) = project_dialog().pick_file() {
+                state.open_document(path);
+            }
+        }
+
+        if ui.button("Save").clicked() {
+            ui.close();
+            save(state);
+        }
+
+        if ui.button("Save As…").clicked() {
+            ui.close();
+            save_as(state);
+        }
+    });
+}
+
+fn save(state: &mut AppState) {
+    match state.document_path.clone() {
+        Some(path) => state.save_document(path),
+        None => save_as(state),
+    }
+}
+
+fn save_as(state: &mut AppState) {
+    let dialog = project_dialog().set_file_name(format!("untitled.{FILE_EXTENSION}"));
+    if let Some(mut path) = dialog.save_file() {
+        if path.extension().is_none() {
+            path.set_extension(FILE_EXTENSION);
+        }
+        state.save_document(path);
+    }
+}
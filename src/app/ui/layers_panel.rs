@@ -0,0 +1,107 @@
+// app/ui/layers_panel.rs
+use eframe::egui::{self, ComboBox};
+use crate::app::blend::BlendMode;
+use crate::app::layers::LayerFlag;
+use crate::app::state::AppState;
+
+/// Side panel listing the layer stack (top layer first) with the active
+/// layer's settings underneath.
+pub fn show(state: &mut AppState, ctx: &egui::Context) {
+    egui::SidePanel::right("layers_panel")
+        .resizable(true)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            ui.heading("Layers");
+
+            ui.horizontal(|ui| {
+                if ui.button("➕").on_hover_text("New layer").clicked() {
+                    state.add_layer();
+                }
+                let can_delete = state.layers.len() > 1;
+                if ui
+                    .add_enabled(can_delete, egui::Button::new("➖"))
+                    .on_hover_text("Delete layer")
+                    .clicked()
+                {
+                    state.delete_active_layer();
+                }
+                let top = state.active_layer + 1 >= state.layers.len();
+                if ui
+                    .add_enabled(!top, egui::Button::new("⏶"))
+                    .on_hover_text("Move up")
+                    .clicked()
+                {
+                    state.move_active_layer(1);
+                }
+                let bottom = state.active_layer == 0;
+                if ui
+                    .add_enabled(!bottom, egui::Button::new("⏷"))
+                    .on_hover_text("Move down")
+                    .clicked()
+                {
+                    state.move_active_layer(-1);
+                }
+                if ui
+                    .add_enabled(!bottom, egui::Button::new("Merge ⏷"))
+                    .on_hover_text("Merge into the layer below")
+                    .clicked()
+                {
+                    state.merge_active_down();
+                }
+            });
+
+            ui.separator();
+
+            // Top of the stack is listed first
+            let mut activate = None;
+            let mut toggled = None;
+            for (i, layer) in state.layers.iter().enumerate().rev() {
+                ui.horizontal(|ui| {
+                    let mut visible = layer.visible;
+                    if ui.checkbox(&mut visible, "").on_hover_text("Visible").changed() {
+                        toggled = Some((i, LayerFlag::Visible, visible));
+                    }
+                    let mut locked = layer.locked;
+                    if ui.toggle_value(&mut locked, "🔒").on_hover_text("Locked").changed() {
+                        toggled = Some((i, LayerFlag::Locked, locked));
+                    }
+                    let mut grow = layer.grow;
+                    if ui.toggle_value(&mut grow, "🌱").on_hover_text("Crystals grow").changed() {
+                        toggled = Some((i, LayerFlag::Grow, grow));
+                    }
+                    if ui.selectable_label(i == state.active_layer, &layer.name).clicked() {
+                        activate = Some(i);
+                    }
+                });
+            }
+            if let Some((i, flag, on)) = toggled {
+                state.set_layer_flag(i, flag, on);
+            }
+            if let Some(i) = activate {
+                state.select_layer(i);
+            }
+
+            ui.separator();
+
+            let layer = &mut state.layers[state.active_layer];
+            egui::Grid::new("active_layer_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut layer.name);
+                ui.end_row();
+
+                ui.label("Opacity");
+                ui.add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Blend");
+                ComboBox::from_id_salt("layer_blend_combobox")
+                    .selected_text(layer.blend.label())
+                    .show_ui(ui, |ui| {
+                        for mode in BlendMode::ALL {
+                            ui.selectable_value(&mut layer.blend, mode, mode.label());
+                        }
+                    });
+                ui.end_row();
+            });
+        });
+}
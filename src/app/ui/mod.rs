@@ -1,8 +1,17 @@
 // app/ui/mod.rs
 
 pub mod dropdown;
+pub mod file_menu;
+pub mod edit_menu;
+pub mod export_menu;
+pub mod view_menu;
+pub mod simulation_menu;
+pub mod replay_menu;
 pub mod top_bar;
 pub mod color_pickers;
 pub mod swatches;
 pub mod mode_buttons;
 pub mod canvas_color_picker;
+pub mod brush_props;
+pub mod preset_picker;
+pub mod layers_panel;
@@ -0,0 +1,80 @@
+// app/ui/preset_picker.rs
+use eframe::egui::{self, ComboBox, Ui};
+use crate::app::presets::PACK_EXTENSION;
+use crate::app::state::AppState;
+
+/// Native file dialog preconfigured for preset packs.
+fn pack_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("Crystal Painter presets", &[PACK_EXTENSION])
+}
+
+/// Preset section of the properties menu: pick, save, delete and share
+/// presets for the active brush.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    let names = state.presets.names(state.active_brush);
+
+    ui.horizontal(|ui| {
+        ui.label("Preset:");
+        let selected = if names.contains(&state.preset_name) {
+            state.preset_name.as_str()
+        } else {
+            "—"
+        };
+
+        let mut picked = None;
+        ComboBox::from_id_salt("brush_preset_combobox")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                if names.is_empty() {
+                    ui.label("No presets saved yet.");
+                }
+                for name in &names {
+                    if ui.selectable_label(*name == state.preset_name, name).clicked() {
+                        picked = Some(name.clone());
+                    }
+                }
+            });
+        if let Some(name) = picked {
+            state.apply_preset(&name);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut state.preset_name)
+                .hint_text("Preset name")
+                .desired_width(120.0),
+        );
+
+        if ui.button("Save current").clicked() {
+            let name = state.preset_name.clone();
+            state.save_preset(&name);
+        }
+
+        let exists = names.contains(&state.preset_name);
+        if ui.add_enabled(exists, egui::Button::new("Delete")).clicked() {
+            let name = state.preset_name.clone();
+            state.delete_preset(&name);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Import pack…").clicked() {
+            ui.close();
+            if let Some(path) = pack_dialog().pick_file() {
+                state.import_presets(path);
+            }
+        }
+
+        if ui.button("Export pack…").clicked() {
+            ui.close();
+            let dialog = pack_dialog().set_file_name(format!("presets.{PACK_EXTENSION}"));
+            if let Some(mut path) = dialog.save_file() {
+                if path.extension().is_none() {
+                    path.set_extension(PACK_EXTENSION);
+                }
+                state.export_presets(path);
+            }
+        }
+    });
+}
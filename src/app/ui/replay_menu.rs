@@ -0,0 +1,67 @@
+// app/ui/replay_menu.rs
+use eframe::egui::{self, Ui};
+use crate::app::replay::REPLAY_EXTENSION;
+use crate::app::state::AppState;
+
+/// Native file dialog preconfigured for input replays.
+fn replay_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("Crystal Painter replay", &[REPLAY_EXTENSION])
+}
+
+/// Replay menu: record the session's input and play recordings back.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    ui.menu_button("Replay", |ui| {
+        if let Some(recorder) = &state.input_recorder {
+            ui.label(format!("Recording: {} events", recorder.event_count()));
+            if ui.button("Stop and save…").clicked() {
+                ui.close();
+                let dialog = replay_dialog().set_file_name(format!("session.{REPLAY_EXTENSION}"));
+                if let Some(mut path) = dialog.save_file() {
+                    if path.extension().is_none() {
+                        path.set_extension(REPLAY_EXTENSION);
+                    }
+                    state.stop_input_recording(path);
+                }
+            }
+            if ui.button("Discard recording").clicked() {
+                ui.close();
+                state.input_recorder = None;
+                state.status = Some("Recording discarded".to_owned());
+            }
+            return;
+        }
+
+        if let Some(player) = &state.player {
+            ui.add(egui::ProgressBar::new(player.progress(state.sim.step)).show_percentage());
+            if ui.button("Stop playback").clicked() {
+                ui.close();
+                state.stop_playback();
+            }
+            return;
+        }
+
+        if ui
+            .button("Start recording")
+            .on_hover_text("Record pointer input, brush and color changes from now on")
+            .clicked()
+        {
+            ui.close();
+            state.start_input_recording();
+        }
+
+        ui.separator();
+
+        ui.checkbox(&mut state.replay_fast, "As fast as possible");
+        ui.checkbox(&mut state.replay_current_props, "Use current brush settings");
+        if ui
+            .button("Play…")
+            .on_hover_text("Replaces the canvas with the recorded session")
+            .clicked()
+        {
+            ui.close();
+            if let Some(path) = replay_dialog().pick_file() {
+                state.start_playback(path);
+            }
+        }
+    });
+}
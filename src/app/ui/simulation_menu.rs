@@ -0,0 +1,58 @@
+// app/ui/simulation_menu.rs
+use eframe::egui::{self, Ui};
+use crate::app::state::AppState;
+
+/// Simulation menu: the seed that makes growth reproducible, the clock and
+/// the containment bounds.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    ui.menu_button("Simulation", |ui| {
+        ui.label(format!("Step {} ({:.1} s)", state.sim.step, state.sim.time()));
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Seed");
+            seed_field(ui, &mut state.sim.seed);
+        });
+
+        if ui
+            .button("Random seed")
+            .on_hover_text("Growth from here on follows the new seed")
+            .clicked()
+        {
+            state.sim.seed = rand::random();
+        }
+
+        ui.separator();
+
+        if ui
+            .button("Contain to view")
+            .on_hover_text("Keep contained growth inside the area in view now")
+            .clicked()
+        {
+            ui.close();
+            state.contain_to_view();
+        }
+    });
+}
+
+/// Text field for the full 64-bit seed, which a `DragValue` (an `f64`
+/// underneath) can't hold exactly. Text that isn't a seed yet is kept while
+/// the field is being edited and dropped when it loses focus.
+fn seed_field(ui: &mut Ui, seed: &mut u64) {
+    let id = ui.make_persistent_id("seed_text");
+    let mut text = ui
+        .data_mut(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| seed.to_string());
+    let response = ui.add(egui::TextEdit::singleline(&mut text).desired_width(160.0));
+    if response.changed() {
+        if let Ok(value) = text.trim().parse() {
+            *seed = value;
+        }
+    }
+    if response.has_focus() {
+        ui.data_mut(|d| d.insert_temp(id, text));
+    } else {
+        ui.data_mut(|d| d.remove::<String>(id));
+    }
+}
@@ -8,26 +8,24 @@ pub fn draw(ui: &mut Ui, state: &mut AppState) {
         //
         // Add swatch button
         //
-        if ui.small_button("+").clicked() {
-            if !state.swatches.contains(&state.current_color)
-                && state.swatches.len() < 32
-            {
-                state.swatches.push(state.current_color);
-                state.selected_swatch = Some(state.swatches.len() - 1);
-            }
+        if ui.small_button("+").clicked()
+            && !state.swatches.contains(&state.current_color)
+            && state.swatches.len() < 32
+        {
+            state.swatches.push(state.current_color);
+            state.selected_swatch = Some(state.swatches.len() - 1);
         }
 
         //
         // Remove swatch button
         //
-        if ui.small_button("-").clicked() {
-            if let Some(_) = state.swatches.pop() {
-                if let Some(sel) = state.selected_swatch {
-                    if sel >= state.swatches.len() {
-                        state.selected_swatch = None;
-                    }
-                }
-            }
+        if ui.small_button("-").clicked()
+            && state.swatches.pop().is_some()
+            && state
+                .selected_swatch
+                .is_some_and(|sel| sel >= state.swatches.len())
+        {
+            state.selected_swatch = None;
         }
 
         //
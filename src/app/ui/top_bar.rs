@@ -1,14 +1,23 @@
 // app/ui/top_bar.rs
-use eframe::egui::{self, Ui};
+use eframe::egui;
 use crate::app::state::AppState;
-use crate::app::ui::{dropdown, color_pickers, swatches, mode_buttons, canvas_color_picker};
+use crate::app::ui::{file_menu, edit_menu, export_menu, view_menu, simulation_menu, replay_menu, dropdown, color_pickers, swatches, mode_buttons, canvas_color_picker};
 
 /// Render the top toolbar. Public entry used by state.rs
 pub fn show(state: &mut AppState, ctx: &egui::Context) {
     egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
         ui.horizontal(|ui| {
+            // Project file menu
+            file_menu::draw(ui, state);
+            edit_menu::draw(ui, state);
+            export_menu::draw(ui, state);
+            view_menu::draw(ui, state);
+            simulation_menu::draw(ui, state);
+            replay_menu::draw(ui, state);
+
             // Properties dropdown
             dropdown::properties_dropdown(ui, state);
+            mode_buttons::draw(ui, state);
 
             ui.separator();
 
@@ -37,27 +46,56 @@ pub fn show(state: &mut AppState, ctx: &egui::Context) {
             ui.separator();
 
             // Contain toggle
-            if ui.button(if state.contain_growth { "Contain: ON" } else { "Contain: OFF" }).clicked() {
+            if ui
+                .button(if state.contain_growth { "Contain: ON" } else { "Contain: OFF" })
+                .on_hover_text("Keep growth inside the outlined bounds (Simulation ▸ Contain to view)")
+                .clicked()
+            {
                 state.contain_growth = !state.contain_growth;
             }
 
             ui.separator();
 
+            // Undo / Redo
+            if ui
+                .add_enabled(state.history.can_undo(), egui::Button::new("Undo"))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                state.undo();
+            }
+            if ui
+                .add_enabled(state.history.can_redo(), egui::Button::new("Redo"))
+                .on_hover_text("Ctrl+Shift+Z")
+                .clicked()
+            {
+                state.redo();
+            }
+
+            ui.separator();
+
             // Destroy + Leave
-            if ui.button("Destroy").clicked() {
+            if ui.button("Destroy").on_hover_text("Clear the active layer").clicked() {
                 state.should_destroy = true;
             }
             if ui.button("Leave").clicked() {
-                state.should_exit = true;
+                state.exit_request();
             }
 
             ui.separator();
 
-            // Blot count display (safe access to AppState.blots)
-            ui.label(format!("Blots: {}", state.blots.len()));
+            // Blot count display across all layers
+            let blots: usize = state.layers.iter().map(|l| l.blots.len()).sum();
+            ui.label(format!("Blots: {blots}"));
 
             ui.separator();
 
+            // Last open/save result
+            if let Some(status) = &state.status {
+                ui.label(status);
+                ui.separator();
+            }
+
             // Swatches (right aligned area)
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 swatches::draw(ui, state);
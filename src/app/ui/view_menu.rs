@@ -0,0 +1,41 @@
+// app/ui/view_menu.rs
+use eframe::egui::{self, Ui};
+use crate::app::state::AppState;
+
+/// View menu: zoom level, fit-to-content and reset.
+pub fn draw(ui: &mut Ui, state: &mut AppState) {
+    // Ctrl+0 resets the view, Ctrl+Shift+0 fits the content
+    let (fit, reset) = ui.ctx().input_mut(|i| {
+        let fit = i.consume_shortcut(&egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::Num0,
+        ));
+        let reset = i.consume_shortcut(&egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND,
+            egui::Key::Num0,
+        ));
+        (fit, reset)
+    });
+    if fit {
+        state.fit_to_content();
+    } else if reset {
+        state.camera.reset();
+    }
+
+    ui.menu_button("View", |ui| {
+        ui.label(format!("Zoom: {:.0}%", state.camera.zoom * 100.0));
+        ui.label("Wheel/pinch zooms, middle- or Space-drag pans.");
+
+        ui.separator();
+
+        if ui.button("Fit to content").clicked() {
+            ui.close();
+            state.fit_to_content();
+        }
+
+        if ui.button("Reset view").clicked() {
+            ui.close();
+            state.camera.reset();
+        }
+    });
+}
@@ -1,3 +1,249 @@
+use eframe::egui::{Pos2, Vec2};
+
+#[allow(dead_code)]
 pub fn clamp<T: PartialOrd>(v: T, min: T, max: T) -> T {
     if v < min { min } else if v > max { max } else { v }
 }
+
+/// Shortest distance from `p` to the segment `a`–`b`.
+pub fn distance_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_sq();
+    if len_sq <= f32::EPSILON {
+        return p.distance(a);
+    }
+    let t = ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+/// Intersection point of segments `a1`–`a2` and `b1`–`b2`, if they cross.
+pub fn segment_intersection(a1: Pos2, a2: Pos2, b1: Pos2, b2: Pos2) -> Option<Pos2> {
+    let r = a2 - a1;
+    let s = b2 - b1;
+    let denom = r.x * s.y - r.y * s.x;
+    if denom.abs() < 1e-9 {
+        return None; // parallel or degenerate
+    }
+
+    let qp = b1 - a1;
+    let t = (qp.x * s.y - qp.y * s.x) / denom;
+    let u = (qp.x * r.y - qp.y * r.x) / denom;
+
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(a1 + r * t)
+    } else {
+        None
+    }
+}
+
+/// Range of `t` in 0–1 for which `a + (b - a) * t` lies within `radius` of the
+/// segment `p`–`q` (a capsule), if the two overlap at all.
+pub fn capsule_overlap(a: Pos2, b: Pos2, p: Pos2, q: Pos2, radius: f32) -> Option<(f32, f32)> {
+    let d = b - a;
+    let mut range: Option<(f32, f32)> = None;
+    let mut include = |lo: f32, hi: f32| {
+        if lo <= hi {
+            range = Some(match range {
+                Some((l, h)) => (l.min(lo), h.max(hi)),
+                None => (lo, hi),
+            });
+        }
+    };
+
+    // Round caps: solve |a + d t - c|² = r²
+    for c in [p, q] {
+        let f = a - c;
+        let qa = d.length_sq();
+        let qb = 2.0 * f.dot(d);
+        let qc = f.length_sq() - radius * radius;
+        if qa <= f32::EPSILON {
+            if qc <= 0.0 {
+                include(0.0, 1.0);
+            }
+            continue;
+        }
+        let disc = qb * qb - 4.0 * qa * qc;
+        if disc >= 0.0 {
+            let root = disc.sqrt();
+            include((-qb - root) / (2.0 * qa), (-qb + root) / (2.0 * qa));
+        }
+    }
+
+    // Straight body: along the axis within 0–len, across it within ±radius
+    let axis = q - p;
+    let len = axis.length();
+    if len > f32::EPSILON {
+        let u = axis / len;
+        let n = u.rot90();
+        let along = linear_range((a - p).dot(u), d.dot(u), 0.0, len);
+        let across = linear_range((a - p).dot(n), d.dot(n), -radius, radius);
+        if let (Some(x), Some(y)) = (along, across) {
+            include(x.0.max(y.0), x.1.min(y.1));
+        }
+    }
+
+    let (lo, hi) = range?;
+    let (lo, hi) = (lo.max(0.0), hi.min(1.0));
+    (lo <= hi).then_some((lo, hi))
+}
+
+/// Range of `t` for which `lo <= base + slope * t <= hi`.
+fn linear_range(base: f32, slope: f32, lo: f32, hi: f32) -> Option<(f32, f32)> {
+    if slope.abs() <= f32::EPSILON {
+        return (lo..=hi).contains(&base).then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+    let t0 = (lo - base) / slope;
+    let t1 = (hi - base) / slope;
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Whether `p` lies inside the closed polygon `poly` (even-odd rule).
+pub fn point_in_polygon(p: Pos2, poly: &[Pos2]) -> bool {
+    let mut inside = false;
+    let mut j = poly.len().wrapping_sub(1);
+    for (i, &a) in poly.iter().enumerate() {
+        let b = poly[j];
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// 2D affine transform `p ↦ x * p.x + y * p.y + t`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine {
+    /// Image of the unit x axis.
+    pub x: Vec2,
+    /// Image of the unit y axis.
+    pub y: Vec2,
+    pub t: Vec2,
+}
+
+impl Affine {
+    pub const IDENTITY: Self = Self {
+        x: Vec2::X,
+        y: Vec2::Y,
+        t: Vec2::ZERO,
+    };
+
+    pub fn translate(offset: Vec2) -> Self {
+        Self {
+            t: offset,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Scale by `factor` per axis, keeping `center` fixed.
+    pub fn scale_about(center: Pos2, factor: Vec2) -> Self {
+        let c = center.to_vec2();
+        Self {
+            x: Vec2::new(factor.x, 0.0),
+            y: Vec2::new(0.0, factor.y),
+            t: c - c * factor,
+        }
+    }
+
+    /// Rotate by `angle` radians, keeping `center` fixed.
+    pub fn rotate_about(center: Pos2, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let linear = Self {
+            x: Vec2::new(cos, sin),
+            y: Vec2::new(-sin, cos),
+            t: Vec2::ZERO,
+        };
+        let c = center.to_vec2();
+        Self {
+            t: c - linear.apply_vec(c),
+            ..linear
+        }
+    }
+
+    pub fn apply(&self, p: Pos2) -> Pos2 {
+        (self.x * p.x + self.y * p.y + self.t).to_pos2()
+    }
+
+    /// Transform a direction or offset (ignores the translation).
+    pub fn apply_vec(&self, v: Vec2) -> Vec2 {
+        self.x * v.x + self.y * v.y
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &Affine) -> Affine {
+        Affine {
+            x: next.apply_vec(self.x),
+            y: next.apply_vec(self.y),
+            t: next.apply_vec(self.t) + next.t,
+        }
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.x.x * self.y.y - self.y.x * self.x.y
+    }
+
+    /// Inverse transform, if this one doesn't collapse the plane.
+    pub fn inverse(&self) -> Option<Affine> {
+        let det = self.determinant();
+        if det.abs() <= f32::EPSILON {
+            return None;
+        }
+        let x = Vec2::new(self.y.y, -self.x.y) / det;
+        let y = Vec2::new(-self.y.x, self.x.x) / det;
+        let linear = Affine { x, y, t: Vec2::ZERO };
+        Some(Affine {
+            t: -linear.apply_vec(self.t),
+            ..linear
+        })
+    }
+
+    /// Factor lengths change by on average, for radii and widths.
+    pub fn mean_scale(&self) -> f32 {
+        self.determinant().abs().sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4
+    }
+
+    #[test]
+    fn capsule_overlap_spans_the_covered_part() {
+        let (a, b) = (Pos2::new(0.0, 0.0), Pos2::new(100.0, 0.0));
+
+        // Across the body
+        let span = capsule_overlap(a, b, Pos2::new(50.0, -20.0), Pos2::new(50.0, 20.0), 10.0);
+        assert!(close(span.unwrap(), (0.4, 0.6)), "{span:?}");
+
+        // Round cap only: the capsule ends just above the segment
+        let span = capsule_overlap(a, b, Pos2::new(50.0, 6.0), Pos2::new(50.0, 40.0), 10.0);
+        assert!(close(span.unwrap(), (0.42, 0.58)), "{span:?}");
+
+        // Clamped to the segment at its end
+        let span = capsule_overlap(a, b, Pos2::new(100.0, -20.0), Pos2::new(100.0, 20.0), 10.0);
+        assert!(close(span.unwrap(), (0.9, 1.0)), "{span:?}");
+
+        assert_eq!(
+            capsule_overlap(a, b, Pos2::new(50.0, 11.0), Pos2::new(50.0, 40.0), 10.0),
+            None
+        );
+    }
+
+    #[test]
+    fn affine_inverse_and_composition() {
+        let p = Pos2::new(3.0, -2.0);
+        let rotate = Affine::rotate_about(Pos2::new(1.0, 1.0), 0.7);
+        let scale = Affine::scale_about(Pos2::new(-4.0, 2.0), Vec2::new(2.0, 0.5));
+        let both = rotate.then(&scale);
+
+        assert!(both.apply(p).distance(scale.apply(rotate.apply(p))) < 1e-4);
+        let back = both.inverse().unwrap().apply(both.apply(p));
+        assert!(back.distance(p) < 1e-4, "{back:?}");
+        assert!((both.mean_scale() - 1.0).abs() < 1e-4);
+        assert_eq!(Affine::scale_about(p, Vec2::new(2.0, 0.0)).inverse(), None);
+    }
+}
@@ -0,0 +1,369 @@
+// app/utils/path.rs
+//! Cleaning up pointer paths: live stabilization while drawing, curve
+//! smoothing and even respacing once a stroke is finished.
+
+use std::collections::VecDeque;
+
+use eframe::egui::{Pos2, Vec2};
+
+/// Steadies pointer input as it arrives.
+///
+/// A lazy-mouse string first drags an anchor behind the pointer, so small
+/// wobbles inside the string's length are ignored; the anchor positions are
+/// then averaged over a short window. With a window of 1 and no string the
+/// input passes through unchanged.
+pub struct Stabilizer {
+    window: VecDeque<Pos2>,
+    size: usize,
+    lazy_radius: f32,
+    anchor: Option<Pos2>,
+}
+
+impl Stabilizer {
+    pub fn new(average: u32, lazy_radius: f32) -> Self {
+        Self {
+            window: VecDeque::new(),
+            size: average.max(1) as usize,
+            lazy_radius: lazy_radius.max(0.0),
+            anchor: None,
+        }
+    }
+
+    /// Start a new path at `pos`.
+    pub fn begin(&mut self, pos: Pos2) {
+        self.window.clear();
+        self.window.push_back(pos);
+        self.anchor = Some(pos);
+    }
+
+    /// Feed a pointer position; returns the next path point, if the
+    /// stabilized position moved.
+    pub fn feed(&mut self, pos: Pos2) -> Option<Pos2> {
+        let anchor = match self.anchor {
+            Some(anchor) if self.lazy_radius > 0.0 => {
+                let pull = pos - anchor;
+                let len = pull.length();
+                if len <= self.lazy_radius {
+                    return None;
+                }
+                anchor + pull * (1.0 - self.lazy_radius / len)
+            }
+            _ => pos,
+        };
+        self.anchor = Some(anchor);
+
+        self.window.push_back(anchor);
+        while self.window.len() > self.size {
+            self.window.pop_front();
+        }
+        let sum = self
+            .window
+            .iter()
+            .fold(Vec2::ZERO, |acc, p| acc + p.to_vec2());
+        Some((sum / self.window.len() as f32).to_pos2())
+    }
+
+    /// Final point closing the gap the averaging lags behind the anchor.
+    pub fn finish(&mut self) -> Option<Pos2> {
+        let anchor = self.anchor.take();
+        let lagging = self.window.len() > 1 && self.size > 1;
+        self.window.clear();
+        anchor.filter(|_| lagging)
+    }
+}
+
+/// How strongly each new speed sample pulls the smoothed pointer speed.
+const SPEED_SMOOTHING: f32 = 0.3;
+
+/// Smoothed pointer speed, measured against the canvas clock.
+#[derive(Clone, Default)]
+pub struct SpeedSampler {
+    /// Last pointer position fed.
+    last_pos: Option<Pos2>,
+
+    /// Canvas clock at the last speed sample.
+    last_time: f64,
+
+    /// Distance moved since the last speed sample. Several input events can
+    /// arrive within one simulation step; their movement adds up until the
+    /// clock advances, so speed doesn't depend on the display's frame rate.
+    unsampled: f32,
+
+    /// Smoothed speed in points per second.
+    speed: f32,
+}
+
+impl SpeedSampler {
+    /// Start measuring at `pos`, from rest.
+    pub fn begin(&mut self, pos: Pos2, time: f64) {
+        *self = Self {
+            last_pos: Some(pos),
+            last_time: time,
+            ..Self::default()
+        };
+    }
+
+    /// Feed a pointer position seen at canvas time `time`.
+    pub fn feed(&mut self, pos: Pos2, time: f64) {
+        let Some(last) = self.last_pos.replace(pos) else {
+            self.last_time = time;
+            return;
+        };
+        self.unsampled += last.distance(pos);
+        let dt = (time - self.last_time) as f32;
+        if dt > 0.0 {
+            self.speed += (self.unsampled / dt - self.speed) * SPEED_SMOOTHING;
+            self.unsampled = 0.0;
+            self.last_time = time;
+        }
+    }
+
+    /// Stop measuring; the next `feed` starts over from its position.
+    pub fn end(&mut self) {
+        self.last_pos = None;
+    }
+
+    pub fn last_pos(&self) -> Option<Pos2> {
+        self.last_pos
+    }
+
+    /// Smoothed speed in points per second.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+}
+
+/// Chaikin corner cutting, `iterations` times. The end points stay put.
+pub fn chaikin(points: &[Pos2], iterations: u32) -> Vec<Pos2> {
+    let mut path = points.to_vec();
+    for _ in 0..iterations {
+        if path.len() < 3 {
+            break;
+        }
+        let mut next = Vec::with_capacity(path.len() * 2);
+        next.push(path[0]);
+        for w in path.windows(2) {
+            next.push(w[0].lerp(w[1], 0.25));
+            next.push(w[0].lerp(w[1], 0.75));
+        }
+        next.push(path[path.len() - 1]);
+        path = next;
+    }
+    path
+}
+
+/// Catmull-Rom spline through every point, sampled about every `step` points.
+pub fn catmull_rom(points: &[Pos2], step: f32) -> Vec<Pos2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let step = step.max(0.1);
+    let at = |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize].to_vec2();
+
+    let mut out = vec![points[0]];
+    for i in 0..points.len() as isize - 1 {
+        let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+        let samples = ((p2 - p1).length() / step).ceil().max(1.0) as u32;
+        for s in 1..samples {
+            let t = s as f32 / samples as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let p = (p1 * 2.0
+                + (p2 - p0) * t
+                + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+                + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+                * 0.5;
+            out.push(p.to_pos2());
+        }
+        out.push(points[i as usize + 1]);
+    }
+    out
+}
+
+/// Respace the path so points sit `spacing` apart along it. The first and
+/// last points are kept; a short remainder is folded into the last span.
+pub fn resample(points: &[Pos2], spacing: f32) -> Vec<Pos2> {
+    if points.len() < 2 || spacing <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut out = vec![points[0]];
+    // Distance travelled since the last emitted point
+    let mut travelled = 0.0;
+    for w in points.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let len = a.distance(b);
+        if len <= f32::EPSILON {
+            continue;
+        }
+        let mut along = spacing - travelled;
+        while along <= len {
+            out.push(a.lerp(b, along / len));
+            along += spacing;
+        }
+        travelled = len - (along - spacing);
+    }
+
+    let last = points[points.len() - 1];
+    if out.len() == 1 || travelled > spacing * 0.5 {
+        out.push(last);
+    } else if let Some(end) = out.last_mut() {
+        *end = last;
+    }
+    out
+}
+
+/// Distance along the path at each point.
+pub fn arc_lengths(points: &[Pos2]) -> Vec<f32> {
+    let mut along = 0.0;
+    let mut prev = points.first().copied();
+    points
+        .iter()
+        .map(|&p| {
+            along += prev.map_or(0.0, |q| q.distance(p));
+            prev = Some(p);
+            along
+        })
+        .collect()
+}
+
+/// Carry `values` given at the points of `from` over to the points of `to`,
+/// matching points by how far along their path they lie (as a fraction of
+/// its length), interpolating in between.
+pub fn transfer(from: &[Pos2], values: &[f32], to: &[Pos2]) -> Vec<f32> {
+    let Some(&first) = values.first() else {
+        return vec![1.0; to.len()];
+    };
+    let from_lengths = arc_lengths(from);
+    let to_lengths = arc_lengths(to);
+    let from_total = from_lengths.last().copied().unwrap_or(0.0);
+    let to_total = to_lengths.last().copied().unwrap_or(0.0);
+    if from_total <= f32::EPSILON || to_total <= f32::EPSILON {
+        return vec![first; to.len()];
+    }
+
+    to_lengths
+        .iter()
+        .map(|&along| {
+            let at = along / to_total * from_total;
+            let i = from_lengths.partition_point(|&l| l < at).min(values.len() - 1);
+            if i == 0 {
+                return values[0];
+            }
+            let (l0, l1) = (from_lengths[i - 1], from_lengths[i]);
+            let t = if l1 > l0 { (at - l0) / (l1 - l0) } else { 1.0 };
+            values[i - 1] + (values[i] - values[i - 1]) * t
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An L-shaped path: 40 points right, then 30 down.
+    fn corner() -> Vec<Pos2> {
+        vec![Pos2::new(0.0, 0.0), Pos2::new(40.0, 0.0), Pos2::new(40.0, 30.0)]
+    }
+
+    fn spans(points: &[Pos2]) -> Vec<f32> {
+        points.windows(2).map(|w| w[0].distance(w[1])).collect()
+    }
+
+    #[test]
+    fn smoothing_keeps_the_end_points() {
+        let path = corner();
+        for smoothed in [chaikin(&path, 3), catmull_rom(&path, 2.0)] {
+            assert_eq!(smoothed.first(), path.first());
+            assert_eq!(smoothed.last(), path.last());
+            assert!(smoothed.len() > path.len());
+        }
+
+        // Chaikin cuts the corner, Catmull-Rom passes through it
+        assert!(!chaikin(&path, 3).contains(&path[1]));
+        assert!(catmull_rom(&path, 2.0).contains(&path[1]));
+        // Sampled evenly in the curve parameter, so only about every step
+        let max = spans(&catmull_rom(&path, 2.0)).into_iter().fold(0.0, f32::max);
+        assert!(max <= 3.0, "{max}");
+    }
+
+    #[test]
+    fn resampled_points_are_evenly_spaced() {
+        let path = corner();
+        let out = resample(&path, 7.0);
+        assert_eq!(out.first(), path.first());
+        assert_eq!(out.last(), path.last());
+
+        // 70 points of path: ten spans, the last one stretched to the end
+        let spans = spans(&out);
+        assert_eq!(spans.len(), 10);
+        for &d in &spans[..spans.len() - 1] {
+            // Spans across the corner cut it short
+            assert!(d > 4.9 && d <= 7.0 + 1e-3, "{d}");
+        }
+    }
+
+    #[test]
+    fn transfer_interpolates_by_fraction_of_length() {
+        let from = [Pos2::new(0.0, 0.0), Pos2::new(10.0, 0.0)];
+        let to = [Pos2::new(0.0, 0.0), Pos2::new(0.0, 5.0), Pos2::new(0.0, 20.0)];
+        let values = transfer(&from, &[1.0, 3.0], &to);
+        assert_eq!(values.len(), to.len());
+        assert!((values[0] - 1.0).abs() < 1e-5);
+        assert!((values[1] - 1.5).abs() < 1e-5);
+        assert!((values[2] - 3.0).abs() < 1e-5);
+
+        assert_eq!(transfer(&from, &[], &to), vec![1.0; 3]);
+    }
+
+    #[test]
+    fn stabilizer_lags_then_converges() {
+        let mut stabilizer = Stabilizer::new(4, 0.0);
+        stabilizer.begin(Pos2::ZERO);
+        let target = Pos2::new(40.0, 0.0);
+        let first = stabilizer.feed(target).unwrap();
+        assert!(first.x > 0.0 && first.x < target.x, "{first:?}");
+
+        let mut last = first;
+        for _ in 0..4 {
+            let next = stabilizer.feed(target).unwrap();
+            assert!(next.x >= last.x);
+            last = next;
+        }
+        assert_eq!(last, target);
+    }
+
+    #[test]
+    fn lazy_stabilizer_ignores_wobble_inside_the_string() {
+        let mut stabilizer = Stabilizer::new(1, 5.0);
+        stabilizer.begin(Pos2::ZERO);
+        assert_eq!(stabilizer.feed(Pos2::new(3.0, 0.0)), None);
+        let pulled = stabilizer.feed(Pos2::new(12.0, 0.0)).unwrap();
+        assert!((pulled.x - 7.0).abs() < 1e-4, "{pulled:?}");
+
+        // Nothing left to catch up on without averaging
+        assert_eq!(stabilizer.finish(), None);
+    }
+
+    #[test]
+    fn speed_counts_movement_between_clock_ticks() {
+        let step = 1.0 / 60.0;
+
+        let mut once = SpeedSampler::default();
+        once.begin(Pos2::ZERO, 0.0);
+        once.feed(Pos2::new(10.0, 0.0), step);
+
+        // A faster display delivers two events within the same step
+        let mut twice = SpeedSampler::default();
+        twice.begin(Pos2::ZERO, 0.0);
+        twice.feed(Pos2::new(5.0, 0.0), 0.0);
+        twice.feed(Pos2::new(10.0, 0.0), step);
+
+        assert!(once.speed() > 0.0);
+        assert!(
+            (once.speed() - twice.speed()).abs() < 1e-3,
+            "{} vs {}",
+            once.speed(),
+            twice.speed()
+        );
+    }
+}
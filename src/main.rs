@@ -3,6 +3,11 @@ mod app;
 use app::state::AppState;
 
 fn main() -> eframe::Result<()> {
+    // `render` and friends run headless and never open a window
+    if let Some(code) = app::cli::run(std::env::args().skip(1)) {
+        std::process::exit(code);
+    }
+
     let options = eframe::NativeOptions::default();
 
     eframe::run_native(